@@ -1,47 +1,91 @@
-use std::{fs::File, io::Write, ops::Neg};
+use std::{
+    f64::consts::{FRAC_PI_2, FRAC_PI_3, FRAC_PI_4},
+    fs::File,
+    io::{BufWriter, Write},
+};
 
-use num::ToPrimitive;
 use raytracer::{
-    canvas::Canvas,
-    color::Color,
-    intersections::{Intersectable, Intersection},
-    matrix::Matrix,
-    rays::Ray,
+    camera::{view_transform, Camera},
+    color::{Color, CommonColor},
+    lights::PointLight,
+    matrix::{Axis, Matrix},
+    planes::Plane,
+    shapes::Shape,
     spheres::Sphere,
-    tuple::{IsTuple, Point},
+    tuple::{IsTuple, Point, Vector},
+    world::World,
 };
 
 fn main() {
-    let ray_origin = Point::new(0.0, 0.0, -5.0);
-    let wall_z = 10.0;
-    let wall_size = 7.0;
-    let canvas_pixels = 100;
-    let pixel_size = wall_size / canvas_pixels.to_f64().unwrap();
-    let half = wall_size / 2.0;
-
-    let mut canvas = Canvas::new(canvas_pixels, canvas_pixels);
-    let color = Color::new(1.0, 0.0, 0.0);
-    let mut shape = Sphere::new(Point::new(0.0, 0.0, 0.0), 1.0);
-
-    // let m = Matrix::scaling(1.0, 0.5, 1.0);
-    let m = (&Matrix::shearing(1.0, 0.0, 0.0, 0.0, 0.0, 0.0) * &Matrix::scaling(0.5, 1.0, 1.0))
-        .unwrap();
-    shape.set_transform(m);
-
-    for y in 0..canvas_pixels {
-        let world_y = half - pixel_size * y.to_f64().unwrap();
-        for x in 0..canvas_pixels {
-            let world_x = half.neg() + pixel_size * x.to_f64().unwrap();
-            let position = Point::new(world_x, world_y, wall_z);
-            let r = Ray::new(ray_origin, (position - ray_origin).normalize());
-            let xs = shape.intersect(r).unwrap();
-            let hit = Intersection::hit(xs);
-            if hit.is_some() {
-                canvas.write_pixel(x, y, color);
-            }
-        }
-    }
-
-    let mut file = File::create("castshadow.ppm").unwrap();
-    file.write_all(canvas.to_ppm().as_bytes()).unwrap();
+    let mut floor = Plane::new();
+    floor.material.color = Color::new(1.0, 0.9, 0.9);
+    floor.material.specular = 0.0;
+
+    let mut left_wall = floor.clone();
+    left_wall.set_transform(
+        Matrix::rotation(Axis::X, FRAC_PI_2)
+            .rotate(Axis::Y, -FRAC_PI_4)
+            .and_then(|m| m.translate(0.0, 0.0, 5.0))
+            .unwrap(),
+    );
+
+    let mut right_wall = floor.clone();
+    right_wall.set_transform(
+        Matrix::rotation(Axis::X, FRAC_PI_2)
+            .rotate(Axis::Y, FRAC_PI_4)
+            .and_then(|m| m.translate(0.0, 0.0, 5.0))
+            .unwrap(),
+    );
+
+    let mut middle = Sphere::default();
+    middle.set_transform(Matrix::translation(-0.5, 1.0, 0.5));
+    middle.material.color = Color::new(0.1, 1.0, 0.5);
+    middle.material.diffuse = 0.7;
+    middle.material.specular = 0.3;
+
+    let mut right = Sphere::default();
+    right.set_transform(
+        Matrix::scaling(0.5, 0.5, 0.5)
+            .translate(1.5, 0.5, -0.5)
+            .unwrap(),
+    );
+    right.material.color = Color::new(0.5, 1.0, 0.1);
+    right.material.diffuse = 0.7;
+    right.material.specular = 0.3;
+
+    let mut left = Sphere::default();
+    left.set_transform(
+        Matrix::scaling(0.33, 0.33, 0.33)
+            .translate(-1.5, 0.33, -0.75)
+            .unwrap(),
+    );
+    left.material.color = Color::new(1.0, 0.8, 0.1);
+    left.material.diffuse = 0.7;
+    left.material.specular = 0.3;
+
+    let light = PointLight::new(Point::new(-10.0, 10.0, -10.0), CommonColor::White.value());
+    let world = World::new(
+        vec![
+            floor.into(),
+            left_wall.into(),
+            right_wall.into(),
+            middle.into(),
+            right.into(),
+            left.into(),
+        ],
+        vec![light],
+    );
+
+    let mut camera = Camera::new(200, 100, FRAC_PI_3);
+    camera.transform = view_transform(
+        Point::new(0.0, 1.5, -5.0),
+        Point::new(0.0, 1.0, 0.0),
+        Vector::new(0.0, 1.0, 0.0),
+    )
+    .unwrap();
+    let canvas = camera.render(&world).unwrap();
+
+    let mut file = BufWriter::new(File::create("castshadow.png").unwrap());
+    canvas.write_png(&mut file).unwrap();
+    file.flush().unwrap();
 }
@@ -1,4 +1,7 @@
-use std::{fs::File, io::Write};
+use std::{
+    fs::File,
+    io::{BufWriter, Write},
+};
 
 use num::ToPrimitive;
 
@@ -57,8 +60,9 @@ pub fn make_clock() {
         );
     }
 
-    let mut file = File::create("clock.ppm").unwrap();
-    file.write_all(canvas.to_ppm().as_bytes()).unwrap();
+    let mut file = BufWriter::new(File::create("clock.png").unwrap());
+    canvas.write_png(&mut file).unwrap();
+    file.flush().unwrap();
 }
 
 fn main() {
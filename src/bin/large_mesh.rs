@@ -0,0 +1,72 @@
+use std::{
+    f64::consts::{FRAC_PI_3, PI},
+    fs::File,
+    io::{BufWriter, Write},
+    time::Instant,
+};
+
+use raytracer::{
+    camera::{view_transform, Camera},
+    color::{Color, CommonColor},
+    groups::Group,
+    lights::PointLight,
+    triangles::Triangle,
+    tuple::{IsTuple, Point, Vector},
+    world::World,
+};
+
+// Triangulated unit sphere with 2 * rings * segments triangles
+fn mesh_sphere(rings: usize, segments: usize) -> Group {
+    let point = |ring: usize, segment: usize| {
+        let theta = PI * ring as f64 / rings as f64;
+        let phi = 2.0 * PI * segment as f64 / segments as f64;
+        Point::new(
+            theta.sin() * phi.cos(),
+            theta.cos(),
+            theta.sin() * phi.sin(),
+        )
+    };
+    let mut g = Group::new();
+    for ring in 0..rings {
+        for segment in 0..segments {
+            let p1 = point(ring, segment);
+            let p2 = point(ring + 1, segment);
+            let p3 = point(ring + 1, segment + 1);
+            let p4 = point(ring, segment + 1);
+            let mut t1 = Triangle::new(p1, p2, p3);
+            t1.material.color = Color::new(0.8, 0.5, 0.3);
+            let mut t2 = Triangle::new(p1, p3, p4);
+            t2.material.color = Color::new(0.8, 0.5, 0.3);
+            g.add_child(t1);
+            g.add_child(t2);
+        }
+    }
+    g
+}
+
+// Times building and rendering a mesh of about 100k triangles, which the
+// bounding volume hierarchy should keep within seconds
+fn main() {
+    let start = Instant::now();
+    let mesh = mesh_sphere(200, 250);
+    let triangles = mesh.children().len();
+    println!("Built {} triangles in {:?}", triangles, start.elapsed());
+
+    let light = PointLight::new(Point::new(-10.0, 10.0, -10.0), CommonColor::White.value());
+    let world = World::new(vec![mesh.into()], vec![light]);
+
+    let mut camera = Camera::new(200, 200, FRAC_PI_3);
+    camera.transform = view_transform(
+        Point::new(0.0, 1.5, -4.0),
+        Point::new(0.0, 0.0, 0.0),
+        Vector::new(0.0, 1.0, 0.0),
+    )
+    .unwrap();
+    let start = Instant::now();
+    let canvas = camera.render(&world).unwrap();
+    println!("Rendered in {:?}", start.elapsed());
+
+    let mut file = BufWriter::new(File::create("large_mesh.png").unwrap());
+    canvas.write_png(&mut file).unwrap();
+    file.flush().unwrap();
+}
@@ -1,4 +1,7 @@
-use std::{fs::File, io::Write};
+use std::{
+    fs::File,
+    io::{BufWriter, Write},
+};
 
 use num::ToPrimitive;
 
@@ -41,8 +44,9 @@ fn projectile_project() {
         };
     }
 
-    let mut file = File::create("projectile.ppm").unwrap();
-    file.write_all(canvas.to_ppm().as_bytes()).unwrap();
+    let mut file = BufWriter::new(File::create("projectile.png").unwrap());
+    canvas.write_png(&mut file).unwrap();
+    file.flush().unwrap();
 }
 
 fn main() {
@@ -1,26 +1,23 @@
-use std::{fs::File, io::Write, ops::Neg};
+use std::{
+    fs::File,
+    io::{BufWriter, Write},
+};
 
-use num::ToPrimitive;
 use raytracer::{
-    canvas::Canvas,
+    camera::{view_transform, Camera},
     color::{Color, CommonColor},
-    intersections::{Intersectable, Intersection},
     lights::PointLight,
     // matrix::Matrix,
-    rays::Ray,
     spheres::Sphere,
-    tuple::{IsTuple, Point},
+    tuple::{IsTuple, Point, Vector},
+    world::World,
 };
 
 fn main() {
-    let ray_origin = Point::new(0.0, 0.0, -5.0);
-    let wall_z = 10.0;
-    let wall_size = 7.0;
     let canvas_pixels = 100;
-    let pixel_size = wall_size / canvas_pixels.to_f64().unwrap();
-    let half = wall_size / 2.0;
+    // Same framing as shooting rays from z = -5 at a 7x7 wall at z = 10
+    let field_of_view = 2.0 * (3.5_f64 / 15.0).atan();
 
-    let mut canvas = Canvas::new(canvas_pixels, canvas_pixels);
     // let color = Color::new(1.0, 0.0, 0.0);
     let mut shape = Sphere::new(Point::new(0.0, 0.0, 0.0), 1.0);
     shape.material.color = Color::new(0.4, 0.8, 0.4);
@@ -31,26 +28,18 @@ fn main() {
     // shape.set_transform(m);
 
     let light = PointLight::new(Point::new(-10.0, -10.0, -10.0), CommonColor::White.value());
+    let world = World::new(vec![shape.into()], vec![light]);
 
-    for y in 0..canvas_pixels {
-        let world_y = half - pixel_size * y.to_f64().unwrap();
-        for x in 0..canvas_pixels {
-            let world_x = half.neg() + pixel_size * x.to_f64().unwrap();
-            let position = Point::new(world_x, world_y, wall_z);
-            let r = Ray::new(ray_origin, (position - ray_origin).normalize());
-            let xs = shape.intersect(r).unwrap();
-            let hit = Intersection::hit(xs);
-            if hit.is_some() {
-                let h = hit.unwrap();
-                let point = r.position(h.time);
-                let normal = h.object.normal_at(point).unwrap();
-                let eye = -r.direction;
-                let color = h.object.material.lighting(light, point, eye, normal);
-                canvas.write_pixel(x, y, color);
-            }
-        }
-    }
+    let mut camera = Camera::new(canvas_pixels, canvas_pixels, field_of_view);
+    camera.transform = view_transform(
+        Point::new(0.0, 0.0, -5.0),
+        Point::new(0.0, 0.0, 0.0),
+        Vector::new(0.0, 1.0, 0.0),
+    )
+    .unwrap();
+    let canvas = camera.render(&world).unwrap();
 
-    let mut file = File::create("sphere_lighting.ppm").unwrap();
-    file.write_all(canvas.to_ppm().as_bytes()).unwrap();
+    let mut file = BufWriter::new(File::create("sphere_lighting.png").unwrap());
+    canvas.write_png(&mut file).unwrap();
+    file.flush().unwrap();
 }
@@ -0,0 +1,269 @@
+use crate::{
+    matrix::Matrix,
+    rays::Ray,
+    tuple::{IsTuple, Point},
+    utils::EPSILON,
+};
+
+/// Axis aligned box between `min` and `max`, inclusive.
+/// Default is an empty box that any point or box can be added to.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BoundingBox {
+    pub min: Point,
+    pub max: Point,
+}
+
+impl Default for BoundingBox {
+    fn default() -> Self {
+        BoundingBox {
+            min: Point::new(f64::INFINITY, f64::INFINITY, f64::INFINITY),
+            max: Point::new(f64::NEG_INFINITY, f64::NEG_INFINITY, f64::NEG_INFINITY),
+        }
+    }
+}
+
+impl BoundingBox {
+    pub fn new(min: Point, max: Point) -> Self {
+        BoundingBox { min, max }
+    }
+
+    /// Smallest box containing all the points
+    pub fn from_points(points: &[Point]) -> Self {
+        let mut b = BoundingBox::default();
+        for p in points {
+            b.add_point(*p);
+        }
+        b
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.min.0.x > self.max.0.x || self.min.0.y > self.max.0.y || self.min.0.z > self.max.0.z
+    }
+
+    /// Whether the box is non-empty and doesn't stretch to infinity on any axis
+    pub fn is_finite(&self) -> bool {
+        let (min, max) = (self.min.0, self.max.0);
+        !self.is_empty()
+            && [min.x, min.y, min.z, max.x, max.y, max.z]
+                .iter()
+                .all(|x| x.is_finite())
+    }
+
+    pub fn add_point(&mut self, p: Point) {
+        let (min, max) = (self.min.0, self.max.0);
+        self.min = Point::new(min.x.min(p.0.x), min.y.min(p.0.y), min.z.min(p.0.z));
+        self.max = Point::new(max.x.max(p.0.x), max.y.max(p.0.y), max.z.max(p.0.z));
+    }
+
+    pub fn merge(&mut self, other: &BoundingBox) {
+        if !other.is_empty() {
+            self.add_point(other.min);
+            self.add_point(other.max);
+        }
+    }
+
+    pub fn contains_point(&self, p: Point) -> bool {
+        let (min, max) = (self.min.0, self.max.0);
+        (min.x..=max.x).contains(&p.0.x)
+            && (min.y..=max.y).contains(&p.0.y)
+            && (min.z..=max.z).contains(&p.0.z)
+    }
+
+    pub fn contains_box(&self, other: &BoundingBox) -> bool {
+        self.contains_point(other.min) && self.contains_point(other.max)
+    }
+
+    pub fn centroid(&self) -> Point {
+        let (min, max) = (self.min.0, self.max.0);
+        Point::new(
+            (min.x + max.x) / 2.0,
+            (min.y + max.y) / 2.0,
+            (min.z + max.z) / 2.0,
+        )
+    }
+
+    /// Bounds of this box once transformed by the matrix.
+    /// Each axis of the result is built up from the range every input axis
+    /// contributes to it, which gives the same box as transforming all eight
+    /// corners while coping with infinite extents.
+    pub fn transform(&self, m: &Matrix<f64>) -> BoundingBox {
+        if self.is_empty() {
+            return *self;
+        }
+        let min = [self.min.0.x, self.min.0.y, self.min.0.z];
+        let max = [self.max.0.x, self.max.0.y, self.max.0.z];
+        let mut out_min = [m[0][3], m[1][3], m[2][3]];
+        let mut out_max = out_min;
+        for row in 0..3 {
+            for col in 0..3 {
+                let a = m[row][col];
+                // Skipping zeros keeps 0 * inf from turning into NaN
+                if a == 0.0 {
+                    continue;
+                }
+                let (e, f) = (a * min[col], a * max[col]);
+                out_min[row] += e.min(f);
+                out_max[row] += e.max(f);
+            }
+        }
+        BoundingBox::new(Point::from(out_min), Point::from(out_max))
+    }
+
+    /// Whether the line along the ray passes through the box. Like shape
+    /// intersections this includes the part of the line behind the origin.
+    pub fn intersects(&self, r: Ray) -> bool {
+        if self.is_empty() {
+            return false;
+        }
+        let (xtmin, xtmax) = check_axis(r.origin.0.x, r.direction.0.x, self.min.0.x, self.max.0.x);
+        let (ytmin, ytmax) = check_axis(r.origin.0.y, r.direction.0.y, self.min.0.y, self.max.0.y);
+        let (ztmin, ztmax) = check_axis(r.origin.0.z, r.direction.0.z, self.min.0.z, self.max.0.z);
+
+        let tmin = xtmin.max(ytmin).max(ztmin);
+        let tmax = xtmax.min(ytmax).min(ztmax);
+        tmin <= tmax + EPSILON
+    }
+}
+
+/// Times at which a ray crosses the planes at `min` and `max` on a single axis
+fn check_axis(origin: f64, direction: f64, min: f64, max: f64) -> (f64, f64) {
+    // Parallel rays are either always or never between the planes
+    if direction.abs() < EPSILON {
+        if origin < min - EPSILON || origin > max + EPSILON {
+            return (f64::INFINITY, f64::NEG_INFINITY);
+        }
+        return (f64::NEG_INFINITY, f64::INFINITY);
+    }
+
+    let t1 = (min - origin) / direction;
+    let t2 = (max - origin) / direction;
+    if t1 > t2 {
+        (t2, t1)
+    } else {
+        (t1, t2)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::{FRAC_PI_4, SQRT_2};
+
+    use crate::{matrix::Axis, tuple::Vector};
+
+    use super::*;
+
+    #[test]
+    fn test_default_is_empty() {
+        let b = BoundingBox::default();
+        assert!(b.is_empty());
+        assert!(!b.is_finite());
+        let r = Ray::new(Point::new(0.0, 0.0, 0.0), Vector::new(0.0, 0.0, 1.0));
+        assert!(!b.intersects(r));
+    }
+
+    #[test]
+    fn test_add_point_and_merge() {
+        let mut b = BoundingBox::default();
+        b.add_point(Point::new(-5.0, 2.0, 0.0));
+        b.add_point(Point::new(7.0, 0.0, -3.0));
+        assert_eq!(Point::new(-5.0, 0.0, -3.0), b.min);
+        assert_eq!(Point::new(7.0, 2.0, 0.0), b.max);
+
+        let mut b1 = BoundingBox::new(Point::new(-5.0, -2.0, 0.0), Point::new(7.0, 4.0, 4.0));
+        let b2 = BoundingBox::new(Point::new(8.0, -7.0, -2.0), Point::new(14.0, 2.0, 8.0));
+        b1.merge(&b2);
+        b1.merge(&BoundingBox::default());
+        assert_eq!(Point::new(-5.0, -7.0, -2.0), b1.min);
+        assert_eq!(Point::new(14.0, 4.0, 8.0), b1.max);
+    }
+
+    #[test]
+    fn test_contains() {
+        let b = BoundingBox::new(Point::new(5.0, -2.0, 0.0), Point::new(11.0, 4.0, 7.0));
+        let cases = [
+            ([5.0, -2.0, 0.0], true),
+            ([11.0, 4.0, 7.0], true),
+            ([8.0, 1.0, 3.0], true),
+            ([3.0, 0.0, 3.0], false),
+            ([8.0, -4.0, 3.0], false),
+            ([8.0, 1.0, -1.0], false),
+            ([13.0, 1.0, 3.0], false),
+            ([8.0, 5.0, 3.0], false),
+            ([8.0, 1.0, 8.0], false),
+        ];
+        for (point, expected) in cases {
+            assert_eq!(expected, b.contains_point(Point::from(point)));
+        }
+
+        let inner = BoundingBox::new(Point::new(6.0, -1.0, 1.0), Point::new(10.0, 3.0, 6.0));
+        let outer = BoundingBox::new(Point::new(4.0, -3.0, -1.0), Point::new(10.0, 3.0, 6.0));
+        assert!(b.contains_box(&inner));
+        assert!(!b.contains_box(&outer));
+    }
+
+    #[test]
+    fn test_transform() {
+        let b = BoundingBox::new(Point::new(-1.0, -1.0, -1.0), Point::new(1.0, 1.0, 1.0));
+        let m = Matrix::rotation(Axis::Y, FRAC_PI_4)
+            .rotate(Axis::X, FRAC_PI_4)
+            .unwrap();
+        let t = b.transform(&m);
+        assert_eq!(
+            Point::new(-SQRT_2, -1.70711, -1.70711).limit_precision(5),
+            t.min.limit_precision(5)
+        );
+        assert_eq!(
+            Point::new(SQRT_2, 1.70711, 1.70711).limit_precision(5),
+            t.max.limit_precision(5)
+        );
+    }
+
+    #[test]
+    fn test_transform_infinite() {
+        let b = BoundingBox::new(
+            Point::new(f64::NEG_INFINITY, 0.0, f64::NEG_INFINITY),
+            Point::new(f64::INFINITY, 0.0, f64::INFINITY),
+        );
+        let t = b.transform(&Matrix::translation(1.0, 2.0, 3.0));
+        assert_eq!(2.0, t.min.0.y);
+        assert_eq!(2.0, t.max.0.y);
+        assert_eq!(f64::NEG_INFINITY, t.min.0.x);
+        assert_eq!(f64::INFINITY, t.max.0.z);
+    }
+
+    #[test]
+    fn test_intersects() {
+        let b = BoundingBox::new(Point::new(5.0, -2.0, 0.0), Point::new(11.0, 4.0, 7.0));
+        let cases = [
+            ([15.0, 1.0, 2.0], [-1.0, 0.0, 0.0], true),
+            ([-5.0, -1.0, 4.0], [1.0, 0.0, 0.0], true),
+            ([7.0, 6.0, 5.0], [0.0, -1.0, 0.0], true),
+            ([9.0, -5.0, 6.0], [0.0, 1.0, 0.0], true),
+            ([8.0, 2.0, 12.0], [0.0, 0.0, -1.0], true),
+            ([6.0, 0.0, -5.0], [0.0, 0.0, 1.0], true),
+            ([8.0, 1.0, 3.5], [0.0, 0.0, 1.0], true),
+            ([9.0, -1.0, -8.0], [2.0, 4.0, 6.0], false),
+            ([8.0, 3.0, -4.0], [6.0, 2.0, 4.0], false),
+            ([9.0, -1.0, -2.0], [4.0, 6.0, 2.0], false),
+            ([4.0, 0.0, 9.0], [0.0, 0.0, -1.0], false),
+            ([8.0, 6.0, -1.0], [0.0, -1.0, 0.0], false),
+            ([12.0, 5.0, 4.0], [-1.0, 0.0, 0.0], false),
+        ];
+        for (origin, direction, expected) in cases {
+            let r = Ray::new(Point::from(origin), Vector::from(direction).normalize());
+            assert_eq!(expected, b.intersects(r));
+        }
+    }
+
+    #[test]
+    fn test_intersects_flat() {
+        // Flat boxes, like those around triangles, can still be hit
+        let b = BoundingBox::new(Point::new(-1.0, 0.0, -1.0), Point::new(1.0, 0.0, 1.0));
+        let r = Ray::new(Point::new(0.5, 1.0, 0.5), Vector::new(0.0, -1.0, 0.0));
+        assert!(b.intersects(r));
+        let r = Ray::new(Point::new(-2.0, 0.0, 0.5), Vector::new(1.0, 0.0, 0.0));
+        assert!(b.intersects(r));
+        let r = Ray::new(Point::new(-2.0, 0.5, 0.5), Vector::new(1.0, 0.0, 0.0));
+        assert!(!b.intersects(r));
+    }
+}
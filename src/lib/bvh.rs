@@ -0,0 +1,220 @@
+use crate::{bounds::BoundingBox, rays::Ray, tuple::Point};
+
+/// Nodes with this many items or fewer aren't split any further
+const MAX_LEAF_SIZE: usize = 4;
+
+#[derive(Debug, Clone)]
+enum NodeKind {
+    /// Range of `Bvh::items` within the leaf
+    Leaf {
+        start: usize,
+        end: usize,
+    },
+    Interior {
+        left: usize,
+        right: usize,
+    },
+}
+
+#[derive(Debug, Clone)]
+struct Node {
+    bounds: BoundingBox,
+    kind: NodeKind,
+}
+
+/// Bounding volume hierarchy over a list of items, given by their bounds.
+/// Items are split at the median of their centroids along the axis the
+/// centroids are most spread out on. Items without finite bounds, like
+/// planes, can't be split so they are checked against every ray.
+#[derive(Debug, Clone, Default)]
+pub struct Bvh {
+    nodes: Vec<Node>,
+    boxes: Vec<BoundingBox>,
+    items: Vec<usize>,
+    unbounded: Vec<usize>,
+    bounds: BoundingBox,
+}
+
+impl Bvh {
+    pub fn build(boxes: &[BoundingBox]) -> Self {
+        let mut bvh = Bvh {
+            boxes: boxes.to_vec(),
+            ..Bvh::default()
+        };
+        for (i, b) in boxes.iter().enumerate() {
+            bvh.bounds.merge(b);
+            if b.is_finite() {
+                bvh.items.push(i);
+            } else if !b.is_empty() {
+                bvh.unbounded.push(i);
+            }
+        }
+        if !bvh.items.is_empty() {
+            bvh.build_node(boxes, 0, bvh.items.len());
+        }
+        bvh
+    }
+
+    fn build_node(&mut self, boxes: &[BoundingBox], start: usize, end: usize) -> usize {
+        let mut bounds = BoundingBox::default();
+        let mut centroids = BoundingBox::default();
+        for &i in self.items[start..end].iter() {
+            bounds.merge(&boxes[i]);
+            centroids.add_point(boxes[i].centroid());
+        }
+
+        let node = self.nodes.len();
+        self.nodes.push(Node {
+            bounds,
+            kind: NodeKind::Leaf { start, end },
+        });
+        let axis = longest_axis(&centroids);
+        // Items sitting on top of each other can't be split apart
+        if end - start <= MAX_LEAF_SIZE
+            || axis_value(centroids.max, axis) <= axis_value(centroids.min, axis)
+        {
+            return node;
+        }
+
+        let mid = (start + end) / 2;
+        self.items[start..end].select_nth_unstable_by(mid - start, |&a, &b| {
+            let a = axis_value(boxes[a].centroid(), axis);
+            a.total_cmp(&axis_value(boxes[b].centroid(), axis))
+        });
+        let left = self.build_node(boxes, start, mid);
+        let right = self.build_node(boxes, mid, end);
+        self.nodes[node].kind = NodeKind::Interior { left, right };
+        node
+    }
+
+    /// Bounds of every item in the hierarchy
+    pub fn bounds(&self) -> BoundingBox {
+        self.bounds
+    }
+
+    /// Indices of the items the ray may hit, skipping every subtree whose
+    /// bounds the ray misses
+    pub fn candidates(&self, r: Ray) -> Vec<usize> {
+        let mut found = self.unbounded.clone();
+        let mut stack = Vec::new();
+        if !self.nodes.is_empty() {
+            stack.push(0);
+        }
+        while let Some(n) = stack.pop() {
+            let node = &self.nodes[n];
+            if !node.bounds.intersects(r) {
+                continue;
+            }
+            match node.kind {
+                NodeKind::Leaf { start, end } => found.extend(
+                    self.items[start..end]
+                        .iter()
+                        .filter(|&&i| self.boxes[i].intersects(r)),
+                ),
+                NodeKind::Interior { left, right } => {
+                    stack.push(right);
+                    stack.push(left);
+                }
+            }
+        }
+        found
+    }
+}
+
+fn axis_value(p: Point, axis: usize) -> f64 {
+    match axis {
+        0 => p.0.x,
+        1 => p.0.y,
+        _ => p.0.z,
+    }
+}
+
+fn longest_axis(b: &BoundingBox) -> usize {
+    let extent = |axis| axis_value(b.max, axis) - axis_value(b.min, axis);
+    (0..3)
+        .max_by(|&a1, &a2| extent(a1).total_cmp(&extent(a2)))
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tuple::{IsTuple, Vector};
+
+    use super::*;
+
+    fn unit_box_at(x: f64, y: f64, z: f64) -> BoundingBox {
+        BoundingBox::new(
+            Point::new(x - 0.5, y - 0.5, z - 0.5),
+            Point::new(x + 0.5, y + 0.5, z + 0.5),
+        )
+    }
+
+    #[test]
+    fn test_empty() {
+        let bvh = Bvh::build(&[]);
+        assert!(bvh.bounds().is_empty());
+        let r = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        assert!(bvh.candidates(r).is_empty());
+    }
+
+    #[test]
+    fn test_candidates() {
+        // 10x10 grid of boxes on the xy plane, 2 units apart
+        let mut boxes = Vec::new();
+        for x in 0..10 {
+            for y in 0..10 {
+                boxes.push(unit_box_at(x as f64 * 2.0, y as f64 * 2.0, 0.0));
+            }
+        }
+        let bvh = Bvh::build(&boxes);
+        assert_eq!(
+            BoundingBox::new(Point::new(-0.5, -0.5, -0.5), Point::new(18.5, 18.5, 0.5)),
+            bvh.bounds()
+        );
+
+        // Along z through the box at (4, 6) only
+        let r = Ray::new(Point::new(4.0, 6.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        assert_eq!(vec![2 * 10 + 3], bvh.candidates(r));
+
+        // Along x through the row at y = 6
+        let r = Ray::new(Point::new(-5.0, 6.0, 0.0), Vector::new(1.0, 0.0, 0.0));
+        let mut found = bvh.candidates(r);
+        found.sort_unstable();
+        assert_eq!((0..10).map(|x| x * 10 + 3).collect::<Vec<_>>(), found);
+
+        // Misses everything
+        let r = Ray::new(Point::new(-5.0, 6.0, 3.0), Vector::new(1.0, 0.0, 0.0));
+        assert!(bvh.candidates(r).is_empty());
+    }
+
+    #[test]
+    fn test_unbounded_and_empty_items() {
+        let plane = BoundingBox::new(
+            Point::new(f64::NEG_INFINITY, 0.0, f64::NEG_INFINITY),
+            Point::new(f64::INFINITY, 0.0, f64::INFINITY),
+        );
+        let boxes = [
+            unit_box_at(0.0, 5.0, 0.0),
+            plane,
+            BoundingBox::default(),
+            unit_box_at(10.0, 5.0, 0.0),
+        ];
+        let bvh = Bvh::build(&boxes);
+        assert_eq!(plane.min, bvh.bounds().min);
+
+        // Planes are always candidates, empty boxes never are
+        let r = Ray::new(Point::new(20.0, 5.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        assert_eq!(vec![1], bvh.candidates(r));
+        let r = Ray::new(Point::new(0.0, 5.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        assert_eq!(vec![1, 0], bvh.candidates(r));
+    }
+
+    #[test]
+    fn test_overlapping_items() {
+        // Identical boxes can't be split, they end up in one leaf
+        let boxes = vec![unit_box_at(1.0, 1.0, 1.0); 10];
+        let bvh = Bvh::build(&boxes);
+        let r = Ray::new(Point::new(1.0, 1.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        assert_eq!(10, bvh.candidates(r).len());
+    }
+}
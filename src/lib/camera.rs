@@ -0,0 +1,230 @@
+use crate::{
+    canvas::Canvas,
+    matrix::{Matrix, MatrixError},
+    rays::Ray,
+    tuple::{IsTuple, Point, Vector},
+    world::World,
+};
+
+/// Transformation that orients the world relative to an eye at `from`
+/// looking towards `to`, with `up` being roughly upwards.
+pub fn view_transform(from: Point, to: Point, up: Vector) -> Result<Matrix<f64>, MatrixError> {
+    let forward = (to - from).normalize();
+    let left = forward.cross(up.normalize());
+    let true_up = left.cross(forward);
+    let orientation = Matrix::from([
+        [left.0.x, left.0.y, left.0.z, 0.0],
+        [true_up.0.x, true_up.0.y, true_up.0.z, 0.0],
+        [-forward.0.x, -forward.0.y, -forward.0.z, 0.0],
+        [0.0, 0.0, 0.0, 1.0],
+    ]);
+    &orientation * &Matrix::translation(-from.0.x, -from.0.y, -from.0.z)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Camera {
+    pub hsize: usize,
+    pub vsize: usize,
+    pub field_of_view: f64,
+    pub transform: Matrix<f64>,
+}
+
+impl Camera {
+    pub fn new(hsize: usize, vsize: usize, field_of_view: f64) -> Self {
+        Camera {
+            hsize,
+            vsize,
+            field_of_view,
+            transform: Matrix::identity(4, 1.0),
+        }
+    }
+
+    /// Half of the width and height of the canvas, one unit in front of the camera
+    fn half_extents(&self) -> (f64, f64) {
+        let half_view = (self.field_of_view / 2.0).tan();
+        let aspect = self.hsize as f64 / self.vsize as f64;
+        if aspect >= 1.0 {
+            (half_view, half_view / aspect)
+        } else {
+            (half_view * aspect, half_view)
+        }
+    }
+
+    /// Size of a single pixel on the canvas, one unit in front of the camera
+    pub fn pixel_size(&self) -> f64 {
+        let (half_width, _) = self.half_extents();
+        (half_width * 2.0) / self.hsize as f64
+    }
+
+    pub fn ray_for_pixel(&self, px: usize, py: usize) -> Result<Ray, MatrixError> {
+        self.ray_for_pixel_with_inverse(&self.transform.inverse()?, px, py)
+    }
+
+    fn ray_for_pixel_with_inverse(
+        &self,
+        inverse: &Matrix<f64>,
+        px: usize,
+        py: usize,
+    ) -> Result<Ray, MatrixError> {
+        let (half_width, half_height) = self.half_extents();
+        let pixel_size = self.pixel_size();
+
+        // Offset from the edge of the canvas to the pixel's center
+        let xoffset = (px as f64 + 0.5) * pixel_size;
+        let yoffset = (py as f64 + 0.5) * pixel_size;
+
+        // Untransformed coordinates of the pixel in world space.
+        // Camera looks towards -z, so +x is to the left.
+        let world_x = half_width - xoffset;
+        let world_y = half_height - yoffset;
+
+        let pixel = (inverse * Point::new(world_x, world_y, -1.0))?;
+        let origin = (inverse * Point::new(0.0, 0.0, 0.0))?;
+        let direction = (pixel - origin).normalize();
+        Ok(Ray::new(origin, direction))
+    }
+
+    pub fn render(&self, world: &World) -> Result<Canvas, MatrixError> {
+        let inverse = self.transform.inverse()?;
+        let mut image = Canvas::new(self.hsize, self.vsize);
+        for y in 0..self.vsize {
+            for x in 0..self.hsize {
+                let ray = self.ray_for_pixel_with_inverse(&inverse, x, y)?;
+                image.write_pixel(x, y, world.color_at(ray)?);
+            }
+        }
+        Ok(image)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::{FRAC_PI_2, FRAC_PI_4};
+
+    use crate::{
+        color::Color, lights::PointLight, matrix::Axis, shapes::Shape, spheres::Sphere,
+        utils::is_eq_float,
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_view_transform_default_orientation() {
+        let from = Point::new(0.0, 0.0, 0.0);
+        let to = Point::new(0.0, 0.0, -1.0);
+        let up = Vector::new(0.0, 1.0, 0.0);
+        assert_eq!(
+            Matrix::identity(4, 1.0),
+            view_transform(from, to, up).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_view_transform_positive_z() {
+        let from = Point::new(0.0, 0.0, 0.0);
+        let to = Point::new(0.0, 0.0, 1.0);
+        let up = Vector::new(0.0, 1.0, 0.0);
+        assert_eq!(
+            Matrix::scaling(-1.0, 1.0, -1.0),
+            view_transform(from, to, up).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_view_transform_moves_world() {
+        let from = Point::new(0.0, 0.0, 8.0);
+        let to = Point::new(0.0, 0.0, 0.0);
+        let up = Vector::new(0.0, 1.0, 0.0);
+        assert_eq!(
+            Matrix::translation(0.0, 0.0, -8.0),
+            view_transform(from, to, up).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_view_transform_arbitrary() {
+        let from = Point::new(1.0, 3.0, 2.0);
+        let to = Point::new(4.0, -2.0, 8.0);
+        let up = Vector::new(1.0, 1.0, 0.0);
+        let expected = Matrix::from([
+            [-0.50709, 0.50709, 0.67612, -2.36643],
+            [0.76772, 0.60609, 0.12122, -2.82843],
+            [-0.35857, 0.59761, -0.71714, 0.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ]);
+        assert_eq!(
+            expected,
+            view_transform(from, to, up).unwrap().limit_precision(5)
+        );
+    }
+
+    #[test]
+    fn test_create() {
+        let c = Camera::new(160, 120, FRAC_PI_2);
+        assert_eq!(160, c.hsize);
+        assert_eq!(120, c.vsize);
+        assert_eq!(FRAC_PI_2, c.field_of_view);
+        assert_eq!(Matrix::identity(4, 1.0), c.transform);
+    }
+
+    #[test]
+    fn test_pixel_size() {
+        let c1 = Camera::new(200, 125, FRAC_PI_2);
+        assert!(is_eq_float(&0.01, &c1.pixel_size()));
+
+        let c2 = Camera::new(125, 200, FRAC_PI_2);
+        assert!(is_eq_float(&0.01, &c2.pixel_size()));
+    }
+
+    #[test]
+    fn test_ray_for_pixel() {
+        let mut c = Camera::new(201, 101, FRAC_PI_2);
+
+        // Ray through the center of the canvas
+        let r1 = c.ray_for_pixel(100, 50).unwrap();
+        assert_eq!(Point::new(0.0, 0.0, 0.0), r1.origin);
+        assert_eq!(Vector::new(0.0, 0.0, -1.0), r1.direction.limit_precision(5));
+
+        // Ray through a corner of the canvas
+        let r2 = c.ray_for_pixel(0, 0).unwrap();
+        assert_eq!(Point::new(0.0, 0.0, 0.0), r2.origin);
+        assert_eq!(
+            Vector::new(0.66519, 0.33259, -0.66851),
+            r2.direction.limit_precision(5)
+        );
+
+        // Ray when the camera is transformed
+        c.transform = Matrix::translation(0.0, -2.0, 5.0)
+            .rotate(Axis::Y, FRAC_PI_4)
+            .unwrap();
+        let r3 = c.ray_for_pixel(100, 50).unwrap();
+        assert_eq!(Point::new(0.0, 2.0, -5.0), r3.origin.limit_precision(5));
+        assert_eq!(
+            Vector::new(2.0_f64.sqrt() / 2.0, 0.0, -(2.0_f64.sqrt()) / 2.0).limit_precision(5),
+            r3.direction.limit_precision(5)
+        );
+    }
+
+    #[test]
+    fn test_render() {
+        let light = PointLight::new(Point::new(-10.0, 10.0, -10.0), Color::new(1.0, 1.0, 1.0));
+        let mut s1 = Sphere::default();
+        s1.material.color = Color::new(0.8, 1.0, 0.6);
+        s1.material.diffuse = 0.7;
+        s1.material.specular = 0.2;
+        let mut s2 = Sphere::default();
+        s2.set_transform(Matrix::scaling(0.5, 0.5, 0.5));
+        let w = World::new(vec![s1.into(), s2.into()], vec![light]);
+
+        let mut c = Camera::new(11, 11, FRAC_PI_2);
+        let from = Point::new(0.0, 0.0, -5.0);
+        let to = Point::new(0.0, 0.0, 0.0);
+        let up = Vector::new(0.0, 1.0, 0.0);
+        c.transform = view_transform(from, to, up).unwrap();
+        let image = c.render(&w).unwrap();
+        assert_eq!(
+            Color::new(0.38066, 0.47583, 0.2855),
+            image.pixel_at(5, 5).limit_precision(5)
+        );
+    }
+}
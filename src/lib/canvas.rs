@@ -1,5 +1,31 @@
-use crate::{color::Color, matrix::Matrix};
+use std::io::{self, Write};
 
+use thiserror::Error;
+
+use crate::{
+    color::Color,
+    matrix::Matrix,
+    png::{ColorType, PngWriter},
+};
+
+/// Plain PPM lines should not be longer than this
+const PPM_LINE_LENGTH: usize = 70;
+
+#[derive(Error, Debug, PartialEq)]
+pub enum PpmError {
+    #[error("Unsupported format {0}")]
+    UnsupportedFormat(String),
+    #[error("Invalid {field} in header: {value}")]
+    InvalidHeader { field: &'static str, value: String },
+    #[error("Maximum value {0} is outside 1..=65535")]
+    InvalidMaxval(usize),
+    #[error("Invalid data: {0}")]
+    InvalidData(String),
+    #[error("Unexpected end of data")]
+    UnexpectedEnd,
+}
+
+#[derive(Debug, Clone, PartialEq)]
 pub struct Canvas {
     data: Matrix<Color>,
 }
@@ -26,23 +52,203 @@ impl Canvas {
         self.data[y][x]
     }
 
+    /// Read a plain (P3) or binary (P6) PPM image, scaling the values to
+    /// 0..1 by the maximum value given in the header
+    pub fn from_ppm(data: &[u8]) -> Result<Canvas, PpmError> {
+        let mut tokens = PpmTokens { data, pos: 0 };
+        let magic = tokens.next().ok_or(PpmError::UnexpectedEnd)?;
+        let binary = match magic.as_str() {
+            "P3" => false,
+            "P6" => true,
+            _ => return Err(PpmError::UnsupportedFormat(magic)),
+        };
+        let width = tokens.header_value("width")?;
+        let height = tokens.header_value("height")?;
+        let maxval = tokens.header_value("maximum value")?;
+        if !(1..=65535).contains(&maxval) {
+            return Err(PpmError::InvalidMaxval(maxval));
+        }
+
+        // Every sample takes at least one byte, which catches truncated files
+        // before allocating a canvas for a bogus size
+        let count = width
+            .checked_mul(height)
+            .and_then(|n| n.checked_mul(3))
+            .filter(|&n| n <= data.len() - tokens.pos)
+            .ok_or(PpmError::UnexpectedEnd)?;
+        let samples = if binary {
+            // A single whitespace character separates the header from the
+            // raster, which uses two bytes per sample for large maximum values
+            match data.get(tokens.pos) {
+                Some(b) if b.is_ascii_whitespace() => {}
+                Some(_) => {
+                    return Err(PpmError::InvalidHeader {
+                        field: "maximum value",
+                        value: String::from_utf8_lossy(&data[tokens.pos..tokens.pos + 1])
+                            .into_owned(),
+                    })
+                }
+                None => return Err(PpmError::UnexpectedEnd),
+            }
+            let raster = &data[tokens.pos + 1..];
+            let size = if maxval < 256 { 1 } else { 2 };
+            if raster.len() < count * size {
+                return Err(PpmError::UnexpectedEnd);
+            }
+            raster
+                .chunks_exact(size)
+                .take(count)
+                .map(|c| c.iter().fold(0, |acc, &b| acc << 8 | b as usize))
+                .collect::<Vec<_>>()
+        } else {
+            let mut samples = Vec::with_capacity(count);
+            for _ in 0..count {
+                let token = tokens.next().ok_or(PpmError::UnexpectedEnd)?;
+                let value = token
+                    .parse()
+                    .map_err(|_| PpmError::InvalidData(format!("invalid sample {}", token)))?;
+                samples.push(value);
+            }
+            samples
+        };
+
+        let mut canvas = Canvas::new(width, height);
+        let mut values = samples.into_iter().map(|v| {
+            if v > maxval {
+                return Err(PpmError::InvalidData(format!(
+                    "sample {} is above the maximum value {}",
+                    v, maxval
+                )));
+            }
+            Ok(v as f64 / maxval as f64)
+        });
+        for y in 0..height {
+            for x in 0..width {
+                let mut next = || values.next().ok_or(PpmError::UnexpectedEnd)?;
+                let color = Color::new(next()?, next()?, next()?);
+                canvas.write_pixel(x, y, color);
+            }
+        }
+        Ok(canvas)
+    }
+
+    /// Plain (P3) PPM image as a string
     pub fn to_ppm(&self) -> String {
-        let mut ppm = format!(
-            "P3\n{} {}\n255\n",
-            self.width().to_string(),
-            self.height().to_string(),
-        );
+        let mut ppm = Vec::new();
+        self.write_ppm(&mut ppm)
+            .expect("Writing to a Vec can't fail");
+        String::from_utf8(ppm).expect("PPM output is ascii")
+    }
+
+    /// Write a plain (P3) PPM image. Every row starts on a new line, and rows
+    /// are wrapped so that no line is longer than 70 characters.
+    pub fn write_ppm<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        write!(w, "P3\n{} {}\n255\n", self.width(), self.height())?;
+        let mut line = String::with_capacity(PPM_LINE_LENGTH + 1);
         for row in self.data.iter() {
-            for (column_index, column) in row.into_iter().enumerate() {
-                ppm.push_str(column.to_scaled_rgb_string().as_str());
-                if column_index < self.data.num_cols() - 1 {
-                    ppm.push(' ');
+            for color in row {
+                for value in color.to_rgb8() {
+                    let value = value.to_string();
+                    if !line.is_empty() {
+                        if line.len() + 1 + value.len() > PPM_LINE_LENGTH {
+                            line.push('\n');
+                            w.write_all(line.as_bytes())?;
+                            line.clear();
+                        } else {
+                            line.push(' ');
+                        }
+                    }
+                    line.push_str(&value);
                 }
             }
-            ppm.push('\n');
+            line.push('\n');
+            w.write_all(line.as_bytes())?;
+            line.clear();
+        }
+        Ok(())
+    }
+
+    /// Write an 8 bit RGB PNG image
+    pub fn write_png<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        let mut png = PngWriter::new(w, self.width(), self.height(), ColorType::Rgb)?;
+        let mut bytes = Vec::with_capacity(self.width() * 3);
+        for row in self.data.iter() {
+            bytes.clear();
+            for color in row {
+                bytes.extend_from_slice(&color.to_rgb8());
+            }
+            png.write_row(&bytes)?;
         }
+        png.finish()?;
+        Ok(())
+    }
+
+    /// Binary (P6) PPM image
+    pub fn to_ppm_binary(&self) -> Vec<u8> {
+        let mut ppm = Vec::new();
+        self.write_ppm_binary(&mut ppm)
+            .expect("Writing to a Vec can't fail");
         ppm
     }
+
+    /// Write a binary (P6) PPM image, one byte per sample
+    pub fn write_ppm_binary<W: Write>(&self, w: &mut W) -> io::Result<()> {
+        write!(w, "P6\n{} {}\n255\n", self.width(), self.height())?;
+        let mut bytes = Vec::with_capacity(self.width() * 3);
+        for row in self.data.iter() {
+            bytes.clear();
+            for color in row {
+                bytes.extend_from_slice(&color.to_rgb8());
+            }
+            w.write_all(&bytes)?;
+        }
+        Ok(())
+    }
+}
+
+/// Whitespace separated tokens of a PPM file, skipping `#` comments
+struct PpmTokens<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl PpmTokens<'_> {
+    fn header_value(&mut self, name: &'static str) -> Result<usize, PpmError> {
+        let token = self.next().ok_or(PpmError::UnexpectedEnd)?;
+        token.parse().map_err(|_| PpmError::InvalidHeader {
+            field: name,
+            value: token,
+        })
+    }
+}
+
+impl Iterator for PpmTokens<'_> {
+    type Item = String;
+
+    fn next(&mut self) -> Option<String> {
+        while let Some(&b) = self.data.get(self.pos) {
+            if b == b'#' {
+                while self.pos < self.data.len() && self.data[self.pos] != b'\n' {
+                    self.pos += 1;
+                }
+            } else if b.is_ascii_whitespace() {
+                self.pos += 1;
+            } else {
+                break;
+            }
+        }
+        let start = self.pos;
+        while self.pos < self.data.len()
+            && !self.data[self.pos].is_ascii_whitespace()
+            && self.data[self.pos] != b'#'
+        {
+            self.pos += 1;
+        }
+        if start == self.pos {
+            return None;
+        }
+        Some(String::from_utf8_lossy(&self.data[start..self.pos]).into_owned())
+    }
 }
 
 #[cfg(test)]
@@ -87,4 +293,187 @@ mod tests {
         let expected = "P3\n5 3\n255\n255 0 0 0 0 0 0 0 0 0 0 0 0 0 0\n0 0 0 0 0 0 0 128 0 0 0 0 0 0 0\n0 0 0 0 0 0 0 0 0 0 0 0 0 0 255\n";
         assert_eq!(*expected, c.to_ppm()[..expected.len()]);
     }
+
+    #[test]
+    fn test_from_ppm() {
+        let ppm = b"P3\n4 3\n255\n\
+            255 127 0  0 127 255  127 255 0  255 255 255\n\
+            0 0 0  255 0 0  0 255 0  0 0 255\n\
+            255 255 0  0 255 255  255 0 255  127 127 127\n";
+        let c = Canvas::from_ppm(ppm).unwrap();
+        assert_eq!(4, c.width());
+        assert_eq!(3, c.height());
+        let cases = [
+            (0, 0, Color::new(1.0, 0.49804, 0.0)),
+            (1, 0, Color::new(0.0, 0.49804, 1.0)),
+            (3, 0, Color::new(1.0, 1.0, 1.0)),
+            (1, 1, Color::new(1.0, 0.0, 0.0)),
+            (3, 2, Color::new(0.49804, 0.49804, 0.49804)),
+        ];
+        for (x, y, expected) in cases {
+            assert_eq!(
+                expected.limit_precision(5),
+                c.pixel_at(x, y).limit_precision(5)
+            );
+        }
+    }
+
+    #[test]
+    fn test_from_ppm_comments_and_maxval() {
+        let ppm = b"P3\n# a comment\n1 1 # trailing\n100\n\n  100   50\n25";
+        let c = Canvas::from_ppm(ppm).unwrap();
+        assert_eq!(Color::new(1.0, 0.5, 0.25), c.pixel_at(0, 0));
+    }
+
+    #[test]
+    fn test_from_ppm_round_trip() {
+        let mut c = Canvas::new(3, 2);
+        c.write_pixel(0, 0, Color::new(1.0, 0.0, 0.0));
+        c.write_pixel(2, 1, Color::new(0.0, 0.2, 1.0));
+        assert_eq!(c, Canvas::from_ppm(c.to_ppm().as_bytes()).unwrap());
+    }
+
+    #[test]
+    fn test_from_ppm_errors() {
+        assert_eq!(
+            Err(PpmError::UnsupportedFormat(String::from("P32"))),
+            Canvas::from_ppm(b"P32\n1 1\n255\n0 0 0\n")
+        );
+        assert_eq!(
+            Err(PpmError::InvalidHeader {
+                field: "height",
+                value: String::from("x")
+            }),
+            Canvas::from_ppm(b"P3\n1 x\n255\n0 0 0\n")
+        );
+        assert_eq!(
+            Err(PpmError::InvalidHeader {
+                field: "width",
+                value: String::from("-1")
+            }),
+            Canvas::from_ppm(b"P6\n-1 1\n255\n\0\0\0")
+        );
+        assert_eq!(
+            Err(PpmError::InvalidMaxval(0)),
+            Canvas::from_ppm(b"P3\n1 1\n0\n0 0 0\n")
+        );
+        assert_eq!(
+            Err(PpmError::InvalidMaxval(65536)),
+            Canvas::from_ppm(b"P6\n1 1\n65536\n\0\0\0\0\0\0")
+        );
+        assert_eq!(Err(PpmError::UnexpectedEnd), Canvas::from_ppm(b"P6\n1 1\n"));
+        assert_eq!(
+            Err(PpmError::UnexpectedEnd),
+            Canvas::from_ppm(b"P6\n2 1\n255\n\0\0\0\0\0")
+        );
+        assert!(matches!(
+            Canvas::from_ppm(b"P6\n1 1\n10\n\0\x0b\0"),
+            Err(PpmError::InvalidData(_))
+        ));
+        assert_eq!(
+            Err(PpmError::UnexpectedEnd),
+            Canvas::from_ppm(b"P3\n100000 100000\n255\n0 0 0\n")
+        );
+        assert!(matches!(
+            Canvas::from_ppm(b"P3\n1 1\n255\n0 256 0\n"),
+            Err(PpmError::InvalidData(_))
+        ));
+        assert_eq!(
+            Err(PpmError::UnexpectedEnd),
+            Canvas::from_ppm(b"P3\n2 1\n255\n0 0 0\n")
+        );
+    }
+
+    #[test]
+    fn test_from_ppm_binary() {
+        let mut ppm = b"P6\n# binary\n2 1\n255\n".to_vec();
+        ppm.extend_from_slice(&[255, 0, 51, 0, 255, 0]);
+        let c = Canvas::from_ppm(&ppm).unwrap();
+        assert_eq!(2, c.width());
+        assert_eq!(1, c.height());
+        assert_eq!(Color::new(1.0, 0.0, 0.2), c.pixel_at(0, 0));
+        assert_eq!(Color::new(0.0, 1.0, 0.0), c.pixel_at(1, 0));
+
+        // Raster bytes that look like whitespace or comments are still data
+        let c = Canvas::from_ppm(b"P6 1 1 255 # \n").unwrap();
+        assert_eq!(
+            Color::new(35.0 / 255.0, 32.0 / 255.0, 10.0 / 255.0),
+            c.pixel_at(0, 0)
+        );
+    }
+
+    #[test]
+    fn test_from_ppm_16_bit() {
+        let mut ppm = b"P6\n1 1\n1000\n".to_vec();
+        ppm.extend_from_slice(&[0x03, 0xe8, 0x01, 0xf4, 0x00, 0x00]);
+        let c = Canvas::from_ppm(&ppm).unwrap();
+        assert_eq!(Color::new(1.0, 0.5, 0.0), c.pixel_at(0, 0));
+
+        let c = Canvas::from_ppm(b"P3 1 1 1000 1000 500 0").unwrap();
+        assert_eq!(Color::new(1.0, 0.5, 0.0), c.pixel_at(0, 0));
+    }
+
+    #[test]
+    fn test_ppm_long_lines() {
+        let mut c = Canvas::new(10, 2);
+        for x in 0..10 {
+            for y in 0..2 {
+                c.write_pixel(x, y, Color::new(1.0, 0.8, 0.6));
+            }
+        }
+        let expected = "P3\n10 2\n255\n\
+            255 204 153 255 204 153 255 204 153 255 204 153 255 204 153 255 204\n\
+            153 255 204 153 255 204 153 255 204 153 255 204 153\n\
+            255 204 153 255 204 153 255 204 153 255 204 153 255 204 153 255 204\n\
+            153 255 204 153 255 204 153 255 204 153 255 204 153\n";
+        let ppm = c.to_ppm();
+        assert_eq!(expected, ppm);
+        assert!(ppm.lines().all(|l| l.len() <= PPM_LINE_LENGTH));
+    }
+
+    #[test]
+    fn test_ppm_ends_with_newline() {
+        let c = Canvas::new(5, 3);
+        assert!(c.to_ppm().ends_with('\n'));
+    }
+
+    #[test]
+    fn test_ppm_binary() {
+        let mut c = Canvas::new(2, 2);
+        c.write_pixel(0, 0, Color::new(1.0, 0.0, 0.0));
+        c.write_pixel(1, 1, Color::new(0.0, 0.2, 1.0));
+        let mut expected = b"P6\n2 2\n255\n".to_vec();
+        expected.extend_from_slice(&[255, 0, 0, 0, 0, 0, 0, 0, 0, 0, 51, 255]);
+        assert_eq!(expected, c.to_ppm_binary());
+        assert_eq!(c, Canvas::from_ppm(&c.to_ppm_binary()).unwrap());
+    }
+
+    #[test]
+    fn test_write_ppm() {
+        let mut c = Canvas::new(3, 2);
+        c.write_pixel(1, 0, Color::new(0.2, 0.4, 0.6));
+        let mut out = Vec::new();
+        c.write_ppm(&mut out).unwrap();
+        assert_eq!(c.to_ppm().as_bytes(), &out[..]);
+    }
+
+    #[test]
+    fn test_write_png() {
+        let mut c = Canvas::new(2, 2);
+        c.write_pixel(0, 0, Color::new(1.0, 0.0, 0.0));
+        c.write_pixel(1, 1, Color::new(0.0, 0.2, 1.0));
+        let mut out = Vec::new();
+        c.write_png(&mut out).unwrap();
+
+        let mut w = PngWriter::new(Vec::new(), 2, 2, ColorType::Rgb).unwrap();
+        w.write_row(&[255, 0, 0, 0, 0, 0]).unwrap();
+        w.write_row(&[0, 0, 0, 0, 51, 255]).unwrap();
+        assert_eq!(w.finish().unwrap(), out);
+        assert_eq!(b"\x89PNG\r\n\x1a\n", &out[..8]);
+    }
+
+    #[test]
+    fn test_write_png_empty() {
+        assert!(Canvas::new(0, 0).write_png(&mut Vec::new()).is_err());
+    }
 }
@@ -37,12 +37,17 @@ impl Color {
     }
 
     pub fn to_scaled_rgb_string(self) -> String {
-        format!(
-            "{} {} {}",
+        let [r, g, b] = self.to_rgb8();
+        format!("{} {} {}", r, g, b)
+    }
+
+    /// Components scaled to 0-255, clamping anything out of range
+    pub fn to_rgb8(self) -> [u8; 3] {
+        [
             scale_to_rgb(self.r),
             scale_to_rgb(self.g),
-            scale_to_rgb(self.b)
-        )
+            scale_to_rgb(self.b),
+        ]
     }
 
     pub fn limit_precision(&self, num_places: i32) -> Self {
@@ -134,4 +139,13 @@ mod tests {
         let e1 = Color::new(1.6, 0.7, 1.0);
         assert_eq!(e1, c1 + c2);
     }
+
+    #[test]
+    fn test_to_rgb8() {
+        assert_eq!([255, 128, 0], Color::new(1.5, 0.5, -0.5).to_rgb8());
+        assert_eq!(
+            "204 153 0",
+            Color::new(0.8, 0.6, 0.0).to_scaled_rgb_string()
+        );
+    }
 }
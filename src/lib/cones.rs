@@ -0,0 +1,233 @@
+use std::ops::Neg;
+
+use crate::{
+    bounds::BoundingBox,
+    cylinders::check_cap,
+    intersections::Intersection,
+    materials::Material,
+    rays::Ray,
+    shapes::{Placement, Shape},
+    tuple::{IsTuple, Point, Vector},
+    utils::EPSILON,
+};
+
+/// Double-napped cone around the y axis in object space, with its tip at
+/// the origin and a radius equal to `|y|`.
+/// Truncated at `minimum` and `maximum` (both excluded) on the y axis,
+/// with end caps when `closed` is set.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cone {
+    placement: Placement,
+    pub material: Material,
+    pub minimum: f64,
+    pub maximum: f64,
+    pub closed: bool,
+}
+
+impl Default for Cone {
+    fn default() -> Self {
+        Cone {
+            placement: Placement::default(),
+            material: Material::default(),
+            minimum: f64::NEG_INFINITY,
+            maximum: f64::INFINITY,
+            closed: false,
+        }
+    }
+}
+
+impl Cone {
+    pub fn new(minimum: f64, maximum: f64, closed: bool) -> Self {
+        Cone {
+            minimum,
+            maximum,
+            closed,
+            ..Cone::default()
+        }
+    }
+
+    fn push_if_within_bounds<'a>(&'a self, r: Ray, t: f64, xs: &mut Vec<Intersection<'a, Cone>>) {
+        let y = r.origin.0.y + t * r.direction.0.y;
+        if self.minimum < y && y < self.maximum {
+            xs.push(Intersection::new(t, self));
+        }
+    }
+
+    fn intersect_caps<'a>(&'a self, r: Ray, xs: &mut Vec<Intersection<'a, Cone>>) {
+        // Caps only matter if the cone is closed and the ray isn't parallel to them
+        if !self.closed || r.direction.0.y.abs() < EPSILON {
+            return;
+        }
+        for y in [self.minimum, self.maximum] {
+            let t = (y - r.origin.0.y) / r.direction.0.y;
+            if check_cap(r, t, y.abs()) {
+                xs.push(Intersection::new(t, self));
+            }
+        }
+    }
+}
+
+impl Shape for Cone {
+    fn placement(&self) -> &Placement {
+        &self.placement
+    }
+
+    fn placement_mut(&mut self) -> &mut Placement {
+        &mut self.placement
+    }
+
+    fn material(&self) -> &Material {
+        &self.material
+    }
+
+    fn material_mut(&mut self) -> &mut Material {
+        &mut self.material
+    }
+
+    fn local_intersect(&self, r: Ray) -> Vec<Intersection<'_, Cone>> {
+        let (o, d) = (r.origin.0, r.direction.0);
+        let a = d.x.powi(2) - d.y.powi(2) + d.z.powi(2);
+        let b = 2.0 * o.x * d.x - 2.0 * o.y * d.y + 2.0 * o.z * d.z;
+        let c = o.x.powi(2) - o.y.powi(2) + o.z.powi(2);
+
+        let mut xs = Vec::new();
+        if a.abs() < EPSILON {
+            // Ray is parallel to one of the cone's halves, so the quadratic
+            // degenerates to a linear equation with at most one root.
+            // If b is zero as well, the ray misses the body altogether.
+            if b.abs() >= EPSILON {
+                self.push_if_within_bounds(r, c.neg() / (2.0 * b), &mut xs);
+            }
+        } else {
+            let discriminant = b.powi(2) - 4.0 * a * c;
+            if discriminant < 0.0 {
+                return xs;
+            }
+
+            let mut t0 = (b.neg() - discriminant.sqrt()) / (2.0 * a);
+            let mut t1 = (b.neg() + discriminant.sqrt()) / (2.0 * a);
+            if t0 > t1 {
+                std::mem::swap(&mut t0, &mut t1);
+            }
+            self.push_if_within_bounds(r, t0, &mut xs);
+            self.push_if_within_bounds(r, t1, &mut xs);
+        }
+
+        self.intersect_caps(r, &mut xs);
+        xs
+    }
+
+    fn local_normal_at(&self, p: Point) -> Vector {
+        let dist = p.0.x.powi(2) + p.0.z.powi(2);
+        if dist < self.maximum.powi(2) && p.0.y >= self.maximum - EPSILON {
+            Vector::new(0.0, 1.0, 0.0)
+        } else if dist < self.minimum.powi(2) && p.0.y <= self.minimum + EPSILON {
+            Vector::new(0.0, -1.0, 0.0)
+        } else {
+            let mut y = dist.sqrt();
+            if p.0.y > 0.0 {
+                y = -y;
+            }
+            Vector::new(p.0.x, y, p.0.z)
+        }
+    }
+
+    // Radius is |y|, so the widest point is at whichever end is further out
+    fn bounds(&self) -> BoundingBox {
+        let r = self.minimum.abs().max(self.maximum.abs());
+        BoundingBox::new(
+            Point::new(-r, self.minimum, -r),
+            Point::new(r, self.maximum, r),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_intersect() {
+        let c = Cone::default();
+        let cases = [
+            ([0.0, 0.0, -5.0], [0.0, 0.0, 1.0], 5.0, 5.0),
+            ([0.0, 0.0, -5.0], [1.0, 1.0, 1.0], 8.66025, 8.66025),
+            ([1.0, 1.0, -5.0], [-0.5, -1.0, 1.0], 4.55006, 49.44994),
+        ];
+        for (origin, direction, t0, t1) in cases {
+            let r = Ray::new(Point::from(origin), Vector::from(direction).normalize());
+            let xs = c.local_intersect(r);
+            assert_eq!(2, xs.len());
+            assert!((t0 - xs[0].time).abs() < EPSILON);
+            assert!((t1 - xs[1].time).abs() < EPSILON);
+        }
+    }
+
+    #[test]
+    fn test_intersect_parallel_to_half() {
+        let c = Cone::default();
+        let r = Ray::new(
+            Point::new(0.0, 0.0, -1.0),
+            Vector::new(0.0, 1.0, 1.0).normalize(),
+        );
+        let xs = c.local_intersect(r);
+        assert_eq!(1, xs.len());
+        assert!((0.35355 - xs[0].time).abs() < EPSILON);
+    }
+
+    #[test]
+    fn test_intersect_caps() {
+        let c = Cone::new(-0.5, 0.5, true);
+        let cases = [
+            ([0.0, 0.0, -5.0], [0.0, 1.0, 0.0], 0),
+            ([0.0, 0.0, -0.25], [0.0, 1.0, 1.0], 2),
+            ([0.0, 0.0, -0.25], [0.0, 1.0, 0.0], 4),
+        ];
+        for (origin, direction, count) in cases {
+            let r = Ray::new(Point::from(origin), Vector::from(direction).normalize());
+            assert_eq!(count, c.local_intersect(r).len());
+        }
+    }
+
+    #[test]
+    fn test_normal() {
+        let c = Cone::default();
+        let cases = [
+            ([0.0, 0.0, 0.0], [0.0, 0.0, 0.0]),
+            ([1.0, 1.0, 1.0], [1.0, -(2.0_f64.sqrt()), 1.0]),
+            ([-1.0, -1.0, 0.0], [-1.0, 1.0, 0.0]),
+        ];
+        for (point, normal) in cases {
+            assert_eq!(Vector::from(normal), c.local_normal_at(Point::from(point)));
+        }
+    }
+
+    #[test]
+    fn test_normal_caps() {
+        let c = Cone::new(-1.0, 2.0, true);
+        assert_eq!(
+            Vector::new(0.0, 1.0, 0.0),
+            c.local_normal_at(Point::new(0.5, 2.0, 0.5))
+        );
+        assert_eq!(
+            Vector::new(0.0, -1.0, 0.0),
+            c.local_normal_at(Point::new(0.5, -1.0, 0.0))
+        );
+    }
+
+    #[test]
+    fn test_bounds() {
+        let b = Cone::default().bounds();
+        assert_eq!(
+            Point::new(f64::NEG_INFINITY, f64::NEG_INFINITY, f64::NEG_INFINITY),
+            b.min
+        );
+        assert_eq!(
+            Point::new(f64::INFINITY, f64::INFINITY, f64::INFINITY),
+            b.max
+        );
+        let b = Cone::new(-5.0, 3.0, false).bounds();
+        assert_eq!(Point::new(-5.0, -5.0, -5.0), b.min);
+        assert_eq!(Point::new(5.0, 3.0, 5.0), b.max);
+    }
+}
@@ -0,0 +1,350 @@
+use std::sync::{Arc, OnceLock};
+
+use crate::{
+    bounds::BoundingBox,
+    intersections::{Intersectable, Intersection},
+    materials::Material,
+    matrix::{Matrix, MatrixError},
+    objects::Object,
+    rays::Ray,
+    shapes::Placement,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CsgOperation {
+    Union,
+    Intersection,
+    Difference,
+}
+
+/// Whether an intersection on the boundary of a CSG shape is kept.
+/// `lhit` is set if the intersection is with the left child, `inl` and `inr`
+/// whether the ray is currently inside the left and right children.
+pub fn intersection_allowed(op: CsgOperation, lhit: bool, inl: bool, inr: bool) -> bool {
+    match op {
+        CsgOperation::Union => (lhit && !inr) || (!lhit && !inl),
+        CsgOperation::Intersection => (lhit && inr) || (!lhit && inl),
+        CsgOperation::Difference => (lhit && !inr) || (!lhit && inl),
+    }
+}
+
+/// Constructive solid geometry: two objects combined by an operation.
+/// Like groups, the children are transformed along with the CSG and keep
+/// their own materials. The bounds of both children are computed on the
+/// first intersection and cached.
+#[derive(Debug, Clone)]
+pub struct Csg {
+    // Shared with both children as their parent, like a group's
+    placement: Arc<Placement>,
+    material: Material,
+    operation: CsgOperation,
+    left: Box<Object>,
+    right: Box<Object>,
+    bounds: OnceLock<BoundingBox>,
+}
+
+impl PartialEq for Csg {
+    fn eq(&self, other: &Self) -> bool {
+        self.placement == other.placement
+            && self.material == other.material
+            && self.operation == other.operation
+            && self.left == other.left
+            && self.right == other.right
+    }
+}
+
+impl Csg {
+    pub fn new<L, R>(operation: CsgOperation, left: L, right: R) -> Self
+    where
+        L: Into<Object>,
+        R: Into<Object>,
+    {
+        let mut csg = Csg {
+            placement: Arc::default(),
+            material: Material::default(),
+            operation,
+            left: Box::new(left.into()),
+            right: Box::new(right.into()),
+            bounds: OnceLock::new(),
+        };
+        csg.update_children();
+        csg
+    }
+
+    pub fn operation(&self) -> CsgOperation {
+        self.operation
+    }
+
+    pub fn left(&self) -> &Object {
+        &self.left
+    }
+
+    pub fn right(&self) -> &Object {
+        &self.right
+    }
+
+    pub fn placement(&self) -> &Placement {
+        &self.placement
+    }
+
+    pub fn transform(&self) -> &Matrix<f64> {
+        self.placement.transform()
+    }
+
+    pub fn set_transform(&mut self, m: Matrix<f64>) {
+        Arc::make_mut(&mut self.placement).set_transform(m);
+        self.update_children();
+    }
+
+    pub fn parent(&self) -> Option<&Placement> {
+        self.placement.parent()
+    }
+
+    pub(crate) fn set_parent(&mut self, parent: Option<Arc<Placement>>) {
+        Arc::make_mut(&mut self.placement).set_parent(parent);
+        self.update_children();
+    }
+
+    /// Material last applied with `set_material`
+    pub fn material(&self) -> &Material {
+        &self.material
+    }
+
+    /// Mutable access to the CSG's own material. Changes are not applied
+    /// to the children, use `set_material` for that.
+    pub fn material_mut(&mut self) -> &mut Material {
+        &mut self.material
+    }
+
+    /// Apply the material to the CSG and both of its children
+    pub fn set_material(&mut self, material: Material) {
+        self.left.set_material(material.clone());
+        self.right.set_material(material.clone());
+        self.material = material;
+    }
+
+    // Point both children at the current placement and drop the bounds
+    fn update_children(&mut self) {
+        self.bounds = OnceLock::new();
+        self.left.set_parent(Some(Arc::clone(&self.placement)));
+        self.right.set_parent(Some(Arc::clone(&self.placement)));
+    }
+
+    /// Bounds of both children, in the CSG's object space
+    pub fn bounds(&self) -> BoundingBox {
+        *self.bounds.get_or_init(|| {
+            let mut b = self.left.parent_space_bounds();
+            b.merge(&self.right.parent_space_bounds());
+            b
+        })
+    }
+
+    /// Keep only the intersections on the surface of the combined shape.
+    /// The intersections must be sorted by time.
+    pub fn filter_intersections<'a>(
+        &self,
+        xs: Vec<Intersection<'a, Object>>,
+    ) -> Vec<Intersection<'a, Object>> {
+        let (mut inl, mut inr) = (false, false);
+        let mut result = Vec::new();
+        for i in xs {
+            let lhit = self.left.includes(i.object);
+            if intersection_allowed(self.operation, lhit, inl, inr) {
+                result.push(i);
+            }
+            if lhit {
+                inl = !inl;
+            } else {
+                inr = !inr;
+            }
+        }
+        result
+    }
+
+    /// Intersections with the combined shape, referring to the primitives
+    /// within it and sorted by time
+    pub fn intersect(&self, r: Ray) -> Result<Vec<Intersection<'_, Object>>, MatrixError> {
+        let local_ray = r.transform(self.placement.inverse()?)?;
+        if !self.bounds().intersects(local_ray) {
+            return Ok(Vec::new());
+        }
+        let mut xs = self.left.intersect(local_ray)?;
+        xs.append(&mut self.right.intersect(local_ray)?);
+        xs.sort_by(|a, b| a.time.total_cmp(&b.time));
+        Ok(self.filter_intersections(xs))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        color::Color,
+        cubes::Cube,
+        groups::Group,
+        shapes::Shape,
+        spheres::Sphere,
+        tuple::{IsTuple, Point, Vector},
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_create() {
+        let c = Csg::new(CsgOperation::Union, Sphere::default(), Cube::new());
+        assert_eq!(CsgOperation::Union, c.operation());
+        assert!(matches!(c.left(), Object::Sphere(_)));
+        assert!(matches!(c.right(), Object::Cube(_)));
+        match c.left() {
+            Object::Sphere(s) => assert_eq!(Some(c.placement()), s.parent()),
+            _ => panic!("Expected a sphere"),
+        }
+    }
+
+    #[test]
+    fn test_children_share_placement() {
+        let mut c = Csg::new(CsgOperation::Union, Sphere::default(), Cube::new());
+        c.set_transform(Matrix::translation(1.0, 0.0, 0.0));
+        let parents = match (c.left(), c.right()) {
+            (Object::Sphere(s), Object::Cube(b)) => [s.parent().unwrap(), b.parent().unwrap()],
+            _ => panic!("Expected a sphere and a cube"),
+        };
+        for parent in parents {
+            assert!(std::ptr::eq(c.placement(), parent));
+        }
+    }
+
+    #[test]
+    fn test_intersection_allowed() {
+        use CsgOperation::*;
+        // lhit, inl, inr, then the result for union, intersection and difference
+        let cases = [
+            (true, true, true, false, true, false),
+            (true, true, false, true, false, true),
+            (true, false, true, false, true, false),
+            (true, false, false, true, false, true),
+            (false, true, true, false, true, true),
+            (false, true, false, false, true, true),
+            (false, false, true, true, false, false),
+            (false, false, false, true, false, false),
+        ];
+        for (lhit, inl, inr, union, intersection, difference) in cases {
+            assert_eq!(union, intersection_allowed(Union, lhit, inl, inr));
+            assert_eq!(
+                intersection,
+                intersection_allowed(Intersection, lhit, inl, inr)
+            );
+            assert_eq!(difference, intersection_allowed(Difference, lhit, inl, inr));
+        }
+    }
+
+    #[test]
+    fn test_filter_intersections() {
+        let cases = [
+            (CsgOperation::Union, 0, 3),
+            (CsgOperation::Intersection, 1, 2),
+            (CsgOperation::Difference, 0, 1),
+        ];
+        for (op, x0, x1) in cases {
+            let c = Csg::new(op, Sphere::default(), Cube::new());
+            let (s1, s2) = (c.left(), c.right());
+            let xs = vec![
+                Intersection::new(1.0, s1),
+                Intersection::new(2.0, s2),
+                Intersection::new(3.0, s1),
+                Intersection::new(4.0, s2),
+            ];
+            let result = c.filter_intersections(xs.clone());
+            assert_eq!(vec![xs[x0], xs[x1]], result);
+        }
+    }
+
+    #[test]
+    fn test_filter_intersections_nested() {
+        // Hits on objects inside a group on the left count as left hits
+        let mut g = Group::new();
+        g.add_child(Sphere::default());
+        let c = Csg::new(CsgOperation::Difference, g, Cube::new());
+        let s1 = match c.left() {
+            Object::Group(g) => &g.children()[0],
+            _ => panic!("Expected a group"),
+        };
+        let s2 = c.right();
+        let xs = vec![
+            Intersection::new(1.0, s1),
+            Intersection::new(2.0, s2),
+            Intersection::new(3.0, s1),
+            Intersection::new(4.0, s2),
+        ];
+        assert_eq!(vec![xs[0], xs[1]], c.filter_intersections(xs.clone()));
+    }
+
+    #[test]
+    fn test_intersect_miss() {
+        let c = Csg::new(CsgOperation::Union, Sphere::default(), Cube::new());
+        let r = Ray::new(Point::new(0.0, 2.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        assert!(c.intersect(r).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_intersect_hit() {
+        let mut s2 = Sphere::default();
+        s2.set_transform(Matrix::translation(0.0, 0.0, 0.5));
+        let c = Csg::new(CsgOperation::Union, Sphere::default(), s2);
+        let r = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        let xs = c.intersect(r).unwrap();
+        assert_eq!(2, xs.len());
+        assert_eq!(4.0, xs[0].time);
+        assert!(std::ptr::eq(c.left(), xs[0].object));
+        assert_eq!(6.5, xs[1].time);
+        assert!(std::ptr::eq(c.right(), xs[1].object));
+    }
+
+    #[test]
+    fn test_transformed() {
+        // Lens: intersection of two overlapping spheres, moved along x
+        let mut s1 = Sphere::default();
+        s1.set_transform(Matrix::translation(0.0, 0.0, -0.5));
+        let mut s2 = Sphere::default();
+        s2.set_transform(Matrix::translation(0.0, 0.0, 0.5));
+        let mut c = Csg::new(CsgOperation::Intersection, s1, s2);
+        c.set_transform(Matrix::translation(3.0, 0.0, 0.0));
+
+        let r = Ray::new(Point::new(3.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        let xs = c.intersect(r).unwrap();
+        assert_eq!(vec![4.5, 5.5], Intersection::intersections(xs.clone()));
+        assert_eq!(
+            Vector::new(0.0, 0.0, -1.0),
+            xs[0].object.normal_at(Point::new(3.0, 0.0, -0.5)).unwrap()
+        );
+
+        let b = c.bounds();
+        assert_eq!(Point::new(-1.0, -1.0, -1.5), b.min);
+        assert_eq!(Point::new(1.0, 1.0, 1.5), b.max);
+    }
+
+    #[test]
+    fn test_bounds_cache_ignored_by_eq() {
+        let c1 = Csg::new(CsgOperation::Union, Sphere::default(), Cube::new());
+        let c2 = c1.clone();
+        c1.bounds();
+        assert_eq!(c1, c2);
+
+        // Moving the CSG still gives bounds in its own space
+        let mut c = c1.clone();
+        c.set_transform(Matrix::translation(5.0, 0.0, 0.0));
+        assert_eq!(c1.bounds(), c.bounds());
+    }
+
+    #[test]
+    fn test_set_material() {
+        let mut c = Csg::new(CsgOperation::Difference, Cube::new(), Sphere::default());
+        let m = Material {
+            color: Color::new(1.0, 0.0, 0.0),
+            ..Material::default()
+        };
+        c.set_material(m.clone());
+        assert_eq!(&m, c.material());
+        assert_eq!(&m, c.left().material());
+        assert_eq!(&m, c.right().material());
+    }
+}
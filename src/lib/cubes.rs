@@ -0,0 +1,182 @@
+use crate::{
+    bounds::BoundingBox,
+    intersections::Intersection,
+    materials::Material,
+    rays::Ray,
+    shapes::{Placement, Shape},
+    tuple::{IsTuple, Point, Vector},
+    utils::EPSILON,
+};
+
+/// Axis aligned cube spanning -1 to 1 on every axis in object space
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Cube {
+    placement: Placement,
+    pub material: Material,
+}
+
+impl Cube {
+    pub fn new() -> Self {
+        Cube::default()
+    }
+}
+
+/// Times at which a ray crosses the two planes at -1 and 1 on a single axis
+fn check_axis(origin: f64, direction: f64) -> (f64, f64) {
+    let tmin_numerator = -1.0 - origin;
+    let tmax_numerator = 1.0 - origin;
+
+    let (tmin, tmax) = if direction.abs() >= EPSILON {
+        (tmin_numerator / direction, tmax_numerator / direction)
+    } else {
+        (
+            tmin_numerator * f64::INFINITY,
+            tmax_numerator * f64::INFINITY,
+        )
+    };
+
+    if tmin > tmax {
+        (tmax, tmin)
+    } else {
+        (tmin, tmax)
+    }
+}
+
+impl Shape for Cube {
+    fn placement(&self) -> &Placement {
+        &self.placement
+    }
+
+    fn placement_mut(&mut self) -> &mut Placement {
+        &mut self.placement
+    }
+
+    fn material(&self) -> &Material {
+        &self.material
+    }
+
+    fn material_mut(&mut self) -> &mut Material {
+        &mut self.material
+    }
+
+    // Slab method: the ray hits the cube if the time ranges it spends
+    // between each pair of faces overlap
+    fn local_intersect(&self, r: Ray) -> Vec<Intersection<'_, Cube>> {
+        let (xtmin, xtmax) = check_axis(r.origin.0.x, r.direction.0.x);
+        let (ytmin, ytmax) = check_axis(r.origin.0.y, r.direction.0.y);
+        let (ztmin, ztmax) = check_axis(r.origin.0.z, r.direction.0.z);
+
+        let tmin = xtmin.max(ytmin).max(ztmin);
+        let tmax = xtmax.min(ytmax).min(ztmax);
+
+        if tmin > tmax {
+            return Vec::new();
+        }
+        vec![Intersection::new(tmin, self), Intersection::new(tmax, self)]
+    }
+
+    // Normal of the face on whose axis the point is furthest from the center
+    fn local_normal_at(&self, p: Point) -> Vector {
+        let (x, y, z) = (p.0.x, p.0.y, p.0.z);
+        let maxc = x.abs().max(y.abs()).max(z.abs());
+        if maxc == x.abs() {
+            Vector::new(x, 0.0, 0.0)
+        } else if maxc == y.abs() {
+            Vector::new(0.0, y, 0.0)
+        } else {
+            Vector::new(0.0, 0.0, z)
+        }
+    }
+
+    fn bounds(&self) -> BoundingBox {
+        BoundingBox::new(Point::new(-1.0, -1.0, -1.0), Point::new(1.0, 1.0, 1.0))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{intersections::Intersectable, matrix::Matrix};
+
+    use super::*;
+
+    #[test]
+    fn test_intersect() {
+        let c = Cube::new();
+        let cases = [
+            // +x, -x, +y, -y, +z, -z and inside
+            ([5.0, 0.5, 0.0], [-1.0, 0.0, 0.0], 4.0, 6.0),
+            ([-5.0, 0.5, 0.0], [1.0, 0.0, 0.0], 4.0, 6.0),
+            ([0.5, 5.0, 0.0], [0.0, -1.0, 0.0], 4.0, 6.0),
+            ([0.5, -5.0, 0.0], [0.0, 1.0, 0.0], 4.0, 6.0),
+            ([0.5, 0.0, 5.0], [0.0, 0.0, -1.0], 4.0, 6.0),
+            ([0.5, 0.0, -5.0], [0.0, 0.0, 1.0], 4.0, 6.0),
+            ([0.0, 0.5, 0.0], [0.0, 0.0, 1.0], -1.0, 1.0),
+        ];
+        for (origin, direction, t1, t2) in cases {
+            let r = Ray::new(Point::from(origin), Vector::from(direction));
+            let xs = c.local_intersect(r);
+            assert_eq!(2, xs.len());
+            assert_eq!(t1, xs[0].time);
+            assert_eq!(t2, xs[1].time);
+        }
+    }
+
+    #[test]
+    fn test_intersect_miss() {
+        let c = Cube::new();
+        let cases = [
+            ([-2.0, 0.0, 0.0], [0.2673, 0.5345, 0.8018]),
+            ([0.0, -2.0, 0.0], [0.8018, 0.2673, 0.5345]),
+            ([0.0, 0.0, -2.0], [0.5345, 0.8018, 0.2673]),
+            ([2.0, 0.0, 2.0], [0.0, 0.0, -1.0]),
+            ([0.0, 2.0, 2.0], [0.0, -1.0, 0.0]),
+            ([2.0, 2.0, 0.0], [-1.0, 0.0, 0.0]),
+        ];
+        for (origin, direction) in cases {
+            let r = Ray::new(Point::from(origin), Vector::from(direction));
+            assert_eq!(0, c.local_intersect(r).len());
+        }
+    }
+
+    #[test]
+    fn test_normal() {
+        let c = Cube::new();
+        let cases = [
+            ([1.0, 0.5, -0.8], [1.0, 0.0, 0.0]),
+            ([-1.0, -0.2, 0.9], [-1.0, 0.0, 0.0]),
+            ([-0.4, 1.0, -0.1], [0.0, 1.0, 0.0]),
+            ([0.3, -1.0, -0.7], [0.0, -1.0, 0.0]),
+            ([-0.6, 0.3, 1.0], [0.0, 0.0, 1.0]),
+            ([0.4, 0.4, -1.0], [0.0, 0.0, -1.0]),
+            ([1.0, 1.0, 1.0], [1.0, 0.0, 0.0]),
+            ([-1.0, -1.0, -1.0], [-1.0, 0.0, 0.0]),
+        ];
+        for (point, normal) in cases {
+            assert_eq!(Vector::from(normal), c.local_normal_at(Point::from(point)));
+        }
+    }
+
+    #[test]
+    fn test_transformed() {
+        let mut c = Cube::new();
+        c.set_transform(
+            Matrix::scaling(2.0, 2.0, 2.0)
+                .translate(0.0, 1.0, 0.0)
+                .unwrap(),
+        );
+        let r = Ray::new(Point::new(0.0, 1.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        let xs = c.intersect(r).unwrap();
+        assert_eq!(vec![3.0, 7.0], Intersection::intersections(xs));
+        assert_eq!(
+            Vector::new(0.0, 1.0, 0.0),
+            c.normal_at(Point::new(0.5, 3.0, 0.5)).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_bounds() {
+        let b = Cube::new().bounds();
+        assert_eq!(Point::new(-1.0, -1.0, -1.0), b.min);
+        assert_eq!(Point::new(1.0, 1.0, 1.0), b.max);
+    }
+}
@@ -0,0 +1,258 @@
+use std::ops::Neg;
+
+use crate::{
+    bounds::BoundingBox,
+    intersections::Intersection,
+    materials::Material,
+    rays::Ray,
+    shapes::{Placement, Shape},
+    tuple::{IsTuple, Point, Vector},
+    utils::EPSILON,
+};
+
+/// Cylinder of radius 1 around the y axis in object space.
+/// Truncated at `minimum` and `maximum` (both excluded) on the y axis,
+/// with end caps when `closed` is set.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cylinder {
+    placement: Placement,
+    pub material: Material,
+    pub minimum: f64,
+    pub maximum: f64,
+    pub closed: bool,
+}
+
+impl Default for Cylinder {
+    fn default() -> Self {
+        Cylinder {
+            placement: Placement::default(),
+            material: Material::default(),
+            minimum: f64::NEG_INFINITY,
+            maximum: f64::INFINITY,
+            closed: false,
+        }
+    }
+}
+
+impl Cylinder {
+    pub fn new(minimum: f64, maximum: f64, closed: bool) -> Self {
+        Cylinder {
+            minimum,
+            maximum,
+            closed,
+            ..Cylinder::default()
+        }
+    }
+
+    fn intersect_caps<'a>(&'a self, r: Ray, xs: &mut Vec<Intersection<'a, Cylinder>>) {
+        // Caps only matter if the cylinder is closed and the ray isn't parallel to them
+        if !self.closed || r.direction.0.y.abs() < EPSILON {
+            return;
+        }
+        for y in [self.minimum, self.maximum] {
+            let t = (y - r.origin.0.y) / r.direction.0.y;
+            if check_cap(r, t, 1.0) {
+                xs.push(Intersection::new(t, self));
+            }
+        }
+    }
+}
+
+/// Whether the ray at time `t` is within `radius` of the y axis
+pub(crate) fn check_cap(r: Ray, t: f64, radius: f64) -> bool {
+    let x = r.origin.0.x + t * r.direction.0.x;
+    let z = r.origin.0.z + t * r.direction.0.z;
+    (x.powi(2) + z.powi(2)) <= radius.powi(2) + EPSILON
+}
+
+impl Shape for Cylinder {
+    fn placement(&self) -> &Placement {
+        &self.placement
+    }
+
+    fn placement_mut(&mut self) -> &mut Placement {
+        &mut self.placement
+    }
+
+    fn material(&self) -> &Material {
+        &self.material
+    }
+
+    fn material_mut(&mut self) -> &mut Material {
+        &mut self.material
+    }
+
+    fn local_intersect(&self, r: Ray) -> Vec<Intersection<'_, Cylinder>> {
+        let mut xs = Vec::new();
+        let a = r.direction.0.x.powi(2) + r.direction.0.z.powi(2);
+
+        // Ray parallel to the y axis can only hit the caps
+        if a.abs() >= EPSILON {
+            let b = 2.0 * r.origin.0.x * r.direction.0.x + 2.0 * r.origin.0.z * r.direction.0.z;
+            let c = r.origin.0.x.powi(2) + r.origin.0.z.powi(2) - 1.0;
+            let discriminant = b.powi(2) - 4.0 * a * c;
+            if discriminant < 0.0 {
+                return xs;
+            }
+
+            let mut t0 = (b.neg() - discriminant.sqrt()) / (2.0 * a);
+            let mut t1 = (b.neg() + discriminant.sqrt()) / (2.0 * a);
+            if t0 > t1 {
+                std::mem::swap(&mut t0, &mut t1);
+            }
+
+            for t in [t0, t1] {
+                let y = r.origin.0.y + t * r.direction.0.y;
+                if self.minimum < y && y < self.maximum {
+                    xs.push(Intersection::new(t, self));
+                }
+            }
+        }
+
+        self.intersect_caps(r, &mut xs);
+        xs
+    }
+
+    fn local_normal_at(&self, p: Point) -> Vector {
+        let dist = p.0.x.powi(2) + p.0.z.powi(2);
+        if dist < 1.0 && p.0.y >= self.maximum - EPSILON {
+            Vector::new(0.0, 1.0, 0.0)
+        } else if dist < 1.0 && p.0.y <= self.minimum + EPSILON {
+            Vector::new(0.0, -1.0, 0.0)
+        } else {
+            Vector::new(p.0.x, 0.0, p.0.z)
+        }
+    }
+
+    fn bounds(&self) -> BoundingBox {
+        BoundingBox::new(
+            Point::new(-1.0, self.minimum, -1.0),
+            Point::new(1.0, self.maximum, 1.0),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default() {
+        let c = Cylinder::default();
+        assert_eq!(f64::NEG_INFINITY, c.minimum);
+        assert_eq!(f64::INFINITY, c.maximum);
+        assert!(!c.closed);
+    }
+
+    #[test]
+    fn test_intersect_miss() {
+        let c = Cylinder::default();
+        let cases = [
+            ([1.0, 0.0, 0.0], [0.0, 1.0, 0.0]),
+            ([0.0, 0.0, 0.0], [0.0, 1.0, 0.0]),
+            ([0.0, 0.0, -5.0], [1.0, 1.0, 1.0]),
+        ];
+        for (origin, direction) in cases {
+            let r = Ray::new(Point::from(origin), Vector::from(direction).normalize());
+            assert_eq!(0, c.local_intersect(r).len());
+        }
+    }
+
+    #[test]
+    fn test_intersect_hit() {
+        let c = Cylinder::default();
+        let cases = [
+            ([1.0, 0.0, -5.0], [0.0, 0.0, 1.0], 5.0, 5.0),
+            ([0.0, 0.0, -5.0], [0.0, 0.0, 1.0], 4.0, 6.0),
+            ([0.5, 0.0, -5.0], [0.1, 1.0, 1.0], 6.80798, 7.08872),
+        ];
+        for (origin, direction, t0, t1) in cases {
+            let r = Ray::new(Point::from(origin), Vector::from(direction).normalize());
+            let xs = c.local_intersect(r);
+            assert_eq!(2, xs.len());
+            assert!((t0 - xs[0].time).abs() < EPSILON);
+            assert!((t1 - xs[1].time).abs() < EPSILON);
+        }
+    }
+
+    #[test]
+    fn test_normal() {
+        let c = Cylinder::default();
+        let cases = [
+            ([1.0, 0.0, 0.0], [1.0, 0.0, 0.0]),
+            ([0.0, 5.0, -1.0], [0.0, 0.0, -1.0]),
+            ([0.0, -2.0, 1.0], [0.0, 0.0, 1.0]),
+            ([-1.0, 1.0, 0.0], [-1.0, 0.0, 0.0]),
+        ];
+        for (point, normal) in cases {
+            assert_eq!(Vector::from(normal), c.local_normal_at(Point::from(point)));
+        }
+    }
+
+    #[test]
+    fn test_intersect_truncated() {
+        let c = Cylinder::new(1.0, 2.0, false);
+        let cases = [
+            ([0.0, 1.5, 0.0], [0.1, 1.0, 0.0], 0),
+            ([0.0, 3.0, -5.0], [0.0, 0.0, 1.0], 0),
+            ([0.0, 0.0, -5.0], [0.0, 0.0, 1.0], 0),
+            ([0.0, 2.0, -5.0], [0.0, 0.0, 1.0], 0),
+            ([0.0, 1.0, -5.0], [0.0, 0.0, 1.0], 0),
+            ([0.0, 1.5, -2.0], [0.0, 0.0, 1.0], 2),
+        ];
+        for (origin, direction, count) in cases {
+            let r = Ray::new(Point::from(origin), Vector::from(direction).normalize());
+            assert_eq!(count, c.local_intersect(r).len());
+        }
+    }
+
+    #[test]
+    fn test_intersect_caps() {
+        let c = Cylinder::new(1.0, 2.0, true);
+        let cases = [
+            ([0.0, 3.0, 0.0], [0.0, -1.0, 0.0], 2),
+            ([0.0, 3.0, -2.0], [0.0, -1.0, 2.0], 2),
+            ([0.0, 4.0, -2.0], [0.0, -1.0, 1.0], 2),
+            ([0.0, 0.0, -2.0], [0.0, 1.0, 2.0], 2),
+            ([0.0, -1.0, -2.0], [0.0, 1.0, 1.0], 2),
+        ];
+        for (origin, direction, count) in cases {
+            let r = Ray::new(Point::from(origin), Vector::from(direction).normalize());
+            assert_eq!(count, c.local_intersect(r).len());
+        }
+    }
+
+    #[test]
+    fn test_normal_caps() {
+        let c = Cylinder::new(1.0, 2.0, true);
+        let cases = [
+            ([0.0, 1.0, 0.0], [0.0, -1.0, 0.0]),
+            ([0.5, 1.0, 0.0], [0.0, -1.0, 0.0]),
+            ([0.0, 1.0, 0.5], [0.0, -1.0, 0.0]),
+            ([0.0, 2.0, 0.0], [0.0, 1.0, 0.0]),
+            ([0.5, 2.0, 0.0], [0.0, 1.0, 0.0]),
+            ([0.0, 2.0, 0.5], [0.0, 1.0, 0.0]),
+        ];
+        for (point, normal) in cases {
+            assert_eq!(Vector::from(normal), c.local_normal_at(Point::from(point)));
+        }
+    }
+
+    #[test]
+    fn test_check_cap() {
+        let r = Ray::new(Point::new(0.0, 3.0, 0.0), Vector::new(0.0, -1.0, 0.0));
+        assert!(check_cap(r, 1.0, 1.0));
+        let r = Ray::new(Point::new(2.0, 3.0, 0.0), Vector::new(0.0, -1.0, 0.0));
+        assert!(!check_cap(r, 1.0, 1.0));
+    }
+
+    #[test]
+    fn test_bounds() {
+        let b = Cylinder::default().bounds();
+        assert_eq!(Point::new(-1.0, f64::NEG_INFINITY, -1.0), b.min);
+        assert_eq!(Point::new(1.0, f64::INFINITY, 1.0), b.max);
+        let b = Cylinder::new(-5.0, 3.0, false).bounds();
+        assert_eq!(Point::new(-1.0, -5.0, -1.0), b.min);
+        assert_eq!(Point::new(1.0, 3.0, 1.0), b.max);
+    }
+}
@@ -0,0 +1,370 @@
+use std::sync::{Arc, OnceLock};
+
+use crate::{
+    bounds::BoundingBox,
+    bvh::Bvh,
+    intersections::{Intersectable, Intersection},
+    materials::Material,
+    matrix::{Matrix, MatrixError},
+    objects::Object,
+    rays::Ray,
+    shapes::Placement,
+};
+
+/// Collection of objects transformed together as a unit.
+/// Groups can be nested, each child's normal is computed through the
+/// transforms of every group above it.
+/// Rays are only tested against the children whose bounds they pass through,
+/// using a hierarchy built over the children on the first intersection.
+#[derive(Debug, Clone, Default)]
+pub struct Group {
+    // Shared with every child as their parent, so adding children doesn't
+    // copy the group's matrices
+    placement: Arc<Placement>,
+    material: Material,
+    children: Vec<Object>,
+    bvh: OnceLock<Bvh>,
+}
+
+// The hierarchy is only a cache, whether it has been built doesn't matter
+impl PartialEq for Group {
+    fn eq(&self, other: &Self) -> bool {
+        self.placement == other.placement
+            && self.material == other.material
+            && self.children == other.children
+    }
+}
+
+impl Group {
+    pub fn new() -> Self {
+        Group::default()
+    }
+
+    pub fn placement(&self) -> &Placement {
+        &self.placement
+    }
+
+    pub fn transform(&self) -> &Matrix<f64> {
+        self.placement.transform()
+    }
+
+    pub fn set_transform(&mut self, m: Matrix<f64>) {
+        Arc::make_mut(&mut self.placement).set_transform(m);
+        self.update_children();
+    }
+
+    pub fn parent(&self) -> Option<&Placement> {
+        self.placement.parent()
+    }
+
+    pub(crate) fn set_parent(&mut self, parent: Option<Arc<Placement>>) {
+        Arc::make_mut(&mut self.placement).set_parent(parent);
+        self.update_children();
+    }
+
+    /// Material last applied with `set_material`
+    pub fn material(&self) -> &Material {
+        &self.material
+    }
+
+    /// Mutable access to the group's own material. Changes are not applied
+    /// to the children, use `set_material` for that.
+    pub fn material_mut(&mut self) -> &mut Material {
+        &mut self.material
+    }
+
+    /// Apply the material to the group and every object within it
+    pub fn set_material(&mut self, material: Material) {
+        for child in self.children.iter_mut() {
+            child.set_material(material.clone());
+        }
+        self.material = material;
+    }
+
+    pub fn children(&self) -> &[Object] {
+        &self.children
+    }
+
+    /// Take the children out of the group. They keep the group's placement
+    /// as their parent until added somewhere else.
+    pub fn into_children(self) -> Vec<Object> {
+        self.children
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.children.is_empty()
+    }
+
+    pub fn add_child<T>(&mut self, child: T)
+    where
+        T: Into<Object>,
+    {
+        let mut child = child.into();
+        child.set_parent(Some(Arc::clone(&self.placement)));
+        self.children.push(child);
+        self.bvh = OnceLock::new();
+    }
+
+    fn bvh(&self) -> &Bvh {
+        self.bvh.get_or_init(|| {
+            let boxes: Vec<BoundingBox> = self
+                .children
+                .iter()
+                .map(|c| c.parent_space_bounds())
+                .collect();
+            Bvh::build(&boxes)
+        })
+    }
+
+    /// Bounds of every child, in the group's object space
+    pub fn bounds(&self) -> BoundingBox {
+        self.bvh().bounds()
+    }
+
+    // Moving the group copies its placement once if children still hold the
+    // old one, hand them the new one
+    fn update_children(&mut self) {
+        for child in self.children.iter_mut() {
+            child.set_parent(Some(Arc::clone(&self.placement)));
+        }
+    }
+
+    /// Intersect the ray with every child. Intersections refer to the
+    /// children themselves and are sorted by time.
+    pub fn intersect(&self, r: Ray) -> Result<Vec<Intersection<'_, Object>>, MatrixError> {
+        let local_ray = r.transform(self.placement.inverse()?)?;
+        let mut xs = Vec::new();
+        for i in self.bvh().candidates(local_ray) {
+            xs.append(&mut self.children[i].intersect(local_ray)?);
+        }
+        xs.sort_by(|a, b| a.time.total_cmp(&b.time));
+        Ok(xs)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::FRAC_PI_2;
+
+    use crate::{
+        color::Color,
+        cylinders::Cylinder,
+        matrix::Axis,
+        shapes::Shape,
+        spheres::Sphere,
+        tuple::{IsTuple, Point, Vector},
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_create() {
+        let g = Group::new();
+        assert_eq!(&Matrix::identity(4, 1.0), g.transform());
+        assert!(g.is_empty());
+        assert!(g.parent().is_none());
+    }
+
+    #[test]
+    fn test_add_child() {
+        let mut g = Group::new();
+        g.set_transform(Matrix::translation(1.0, 2.0, 3.0));
+        g.add_child(Sphere::default());
+        assert_eq!(1, g.children().len());
+        match &g.children()[0] {
+            Object::Sphere(s) => assert_eq!(Some(g.placement()), s.parent()),
+            _ => panic!("Expected a sphere"),
+        }
+    }
+
+    #[test]
+    fn test_children_share_placement() {
+        let mut g = Group::new();
+        g.add_child(Sphere::default());
+        g.add_child(Sphere::default());
+        g.set_transform(Matrix::translation(1.0, 0.0, 0.0));
+        g.add_child(Sphere::default());
+        let parents: Vec<&Placement> = g
+            .children()
+            .iter()
+            .map(|c| match c {
+                Object::Sphere(s) => s.parent().unwrap(),
+                _ => panic!("Expected a sphere"),
+            })
+            .collect();
+        for parent in parents {
+            assert!(std::ptr::eq(g.placement(), parent));
+        }
+    }
+
+    #[test]
+    fn test_into_children() {
+        let mut g = Group::new();
+        g.add_child(Sphere::default());
+        g.add_child(Group::new());
+        let children = g.into_children();
+        assert_eq!(2, children.len());
+        assert!(matches!(children[0], Object::Sphere(_)));
+        assert!(matches!(children[1], Object::Group(_)));
+    }
+
+    #[test]
+    fn test_set_transform_updates_children() {
+        let mut g = Group::new();
+        g.add_child(Sphere::default());
+        g.set_transform(Matrix::scaling(2.0, 2.0, 2.0));
+        match &g.children()[0] {
+            Object::Sphere(s) => assert_eq!(Some(g.placement()), s.parent()),
+            _ => panic!("Expected a sphere"),
+        }
+    }
+
+    #[test]
+    fn test_intersect_empty() {
+        let g = Group::new();
+        let r = Ray::new(Point::new(0.0, 0.0, 0.0), Vector::new(0.0, 0.0, 1.0));
+        assert_eq!(0, g.intersect(r).unwrap().len());
+    }
+
+    #[test]
+    fn test_intersect() {
+        let mut g = Group::new();
+        let s1 = Sphere::default();
+        let mut s2 = Sphere::default();
+        s2.set_transform(Matrix::translation(0.0, 0.0, -3.0));
+        let mut s3 = Sphere::default();
+        s3.set_transform(Matrix::translation(5.0, 0.0, 0.0));
+        g.add_child(s1);
+        g.add_child(s2);
+        g.add_child(s3);
+
+        let r = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        let xs = g.intersect(r).unwrap();
+        assert_eq!(4, xs.len());
+        assert!(std::ptr::eq(&g.children()[1], xs[0].object));
+        assert!(std::ptr::eq(&g.children()[1], xs[1].object));
+        assert!(std::ptr::eq(&g.children()[0], xs[2].object));
+        assert!(std::ptr::eq(&g.children()[0], xs[3].object));
+    }
+
+    #[test]
+    fn test_intersect_transformed() {
+        let mut g = Group::new();
+        g.set_transform(Matrix::scaling(2.0, 2.0, 2.0));
+        let mut s = Sphere::default();
+        s.set_transform(Matrix::translation(5.0, 0.0, 0.0));
+        g.add_child(s);
+
+        let r = Ray::new(Point::new(10.0, 0.0, -10.0), Vector::new(0.0, 0.0, 1.0));
+        assert_eq!(2, g.intersect(r).unwrap().len());
+    }
+
+    fn nested_sphere() -> Group {
+        let mut g1 = Group::new();
+        g1.set_transform(Matrix::rotation(Axis::Y, FRAC_PI_2));
+        let mut g2 = Group::new();
+        g2.set_transform(Matrix::scaling(1.0, 2.0, 3.0));
+        let mut s = Sphere::default();
+        s.set_transform(Matrix::translation(5.0, 0.0, 0.0));
+        g2.add_child(s);
+        g1.add_child(g2);
+        g1
+    }
+
+    fn inner_sphere(g1: &Group) -> &Object {
+        match &g1.children()[0] {
+            Object::Group(g2) => &g2.children()[0],
+            _ => panic!("Expected a group"),
+        }
+    }
+
+    #[test]
+    fn test_normal_nested() {
+        let g1 = nested_sphere();
+        let n = inner_sphere(&g1)
+            .normal_at(Point::new(1.7321, 1.1547, -5.5774))
+            .unwrap();
+        assert_eq!(
+            Vector::new(0.2857, 0.4286, -0.8571).limit_precision(3),
+            n.limit_precision(3)
+        );
+    }
+
+    #[test]
+    fn test_nested_group_transformed_after_adding() {
+        // Moving the outer group must reach the innermost shape
+        let mut g1 = Group::new();
+        let mut g2 = Group::new();
+        let mut s = Sphere::default();
+        s.set_transform(Matrix::translation(5.0, 0.0, 0.0));
+        g2.add_child(s);
+        g1.add_child(g2);
+        if let Object::Group(g2) = &mut g1.children[0] {
+            g2.set_transform(Matrix::scaling(1.0, 2.0, 3.0));
+        }
+        g1.set_transform(Matrix::rotation(Axis::Y, FRAC_PI_2));
+        let n = inner_sphere(&g1)
+            .normal_at(Point::new(1.7321, 1.1547, -5.5774))
+            .unwrap();
+        assert_eq!(
+            Vector::new(0.2857, 0.4286, -0.8571).limit_precision(3),
+            n.limit_precision(3)
+        );
+    }
+
+    #[test]
+    fn test_bounds() {
+        let mut g = Group::new();
+        let mut s = Sphere::default();
+        s.set_transform(
+            Matrix::scaling(2.0, 2.0, 2.0)
+                .translate(2.0, 5.0, -3.0)
+                .unwrap(),
+        );
+        let mut c = Cylinder::new(-2.0, 2.0, false);
+        c.set_transform(
+            Matrix::scaling(0.5, 1.0, 0.5)
+                .translate(-4.0, -1.0, 4.0)
+                .unwrap(),
+        );
+        g.add_child(s);
+        g.add_child(c);
+        let b = g.bounds();
+        assert_eq!(Point::new(-4.5, -3.0, -5.0), b.min);
+        assert_eq!(Point::new(4.0, 7.0, 4.5), b.max);
+        assert!(Group::new().bounds().is_empty());
+    }
+
+    #[test]
+    fn test_intersect_skips_children_outside_bounds() {
+        // Far more children than a single leaf, in a row along x
+        let mut g = Group::new();
+        for x in 0..20 {
+            let mut s = Sphere::default();
+            s.set_transform(Matrix::translation(x as f64 * 3.0, 0.0, 0.0));
+            g.add_child(s);
+        }
+        let r = Ray::new(Point::new(30.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        assert_eq!(vec![10], g.bvh().candidates(r));
+        let xs = g.intersect(r).unwrap();
+        assert_eq!(vec![4.0, 6.0], Intersection::intersections(xs));
+
+        // Adding a child after intersecting rebuilds the hierarchy
+        let mut s = Sphere::default();
+        s.set_transform(Matrix::translation(30.0, 0.0, 3.0));
+        g.add_child(s);
+        assert_eq!(4, g.intersect(r).unwrap().len());
+    }
+
+    #[test]
+    fn test_set_material() {
+        let mut g = nested_sphere();
+        let m = Material {
+            color: Color::new(1.0, 0.0, 0.0),
+            ..Material::default()
+        };
+        g.set_material(m.clone());
+        assert_eq!(&m, g.material());
+        assert_eq!(&m, inner_sphere(&g).material());
+    }
+}
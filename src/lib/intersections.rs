@@ -1,23 +1,65 @@
-use crate::{matrix::MatrixError, rays::Ray};
+use crate::{
+    matrix::MatrixError,
+    objects::Object,
+    rays::Ray,
+    tuple::{Point, Vector},
+    utils::EPSILON,
+};
 
 pub trait Intersectable {
-    fn intersect(&self, r: Ray) -> Result<Vec<Intersection<Self>>, MatrixError>
+    fn intersect(&self, r: Ray) -> Result<Vec<Intersection<'_, Self>>, MatrixError>
     where
         Self: Sized;
 }
 
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, PartialEq)]
 pub struct Intersection<'a, T> {
     pub time: f64,
     pub object: &'a T,
+    /// Barycentric coordinates of the hit, for shapes that provide them
+    pub u: Option<f64>,
+    pub v: Option<f64>,
 }
 
+// Only holds a reference to the object, so it is copyable whatever the object type
+impl<T> Clone for Intersection<'_, T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for Intersection<'_, T> {}
+
 impl<'a, T> Intersection<'a, T>
 where
     T: Intersectable,
 {
     pub fn new(time: f64, object: &'a T) -> Self {
-        Intersection { time, object }
+        Intersection {
+            time,
+            object,
+            u: None,
+            v: None,
+        }
+    }
+
+    pub fn with_uv(time: f64, object: &'a T, u: f64, v: f64) -> Self {
+        Intersection {
+            time,
+            object,
+            u: Some(u),
+            v: Some(v),
+        }
+    }
+
+    /// Same intersection, attributed to another object
+    pub fn with_object<'b, U>(self, object: &'b U) -> Intersection<'b, U> {
+        Intersection {
+            time: self.time,
+            object,
+            u: self.u,
+            v: self.v,
+        }
     }
 
     pub fn intersections(is: Vec<Intersection<T>>) -> Vec<f64> {
@@ -30,7 +72,7 @@ where
 
     pub fn hit(is: Vec<Intersection<T>>) -> Option<Intersection<T>> {
         let mut result: Option<Intersection<T>> = None;
-        let mut lowest_time = std::f64::MAX;
+        let mut lowest_time = f64::MAX;
         for i in is {
             if i.time >= 0.0 && i.time <= lowest_time {
                 lowest_time = i.time;
@@ -40,3 +82,113 @@ where
         result
     }
 }
+
+/// Precomputed state of a hit, shared by all the shading steps
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Computations<'a> {
+    pub time: f64,
+    pub object: &'a Object,
+    pub point: Point,
+    /// Point nudged above the surface, used for shadow and reflection rays
+    pub over_point: Point,
+    /// Point nudged below the surface, used as the origin of refracted rays
+    pub under_point: Point,
+    pub eyev: Vector,
+    pub normalv: Vector,
+    pub reflectv: Vector,
+    /// Whether the hit occurred from inside the object
+    pub inside: bool,
+    /// Refractive index of the material being exited
+    pub n1: f64,
+    /// Refractive index of the material being entered
+    pub n2: f64,
+}
+
+impl<'a> Intersection<'a, Object> {
+    /// Precompute the hit state. `xs` are all the intersections along the
+    /// ray (including this one), used to find the refractive indices on
+    /// either side of the hit.
+    pub fn prepare_computations(
+        &self,
+        r: Ray,
+        xs: &[Intersection<'a, Object>],
+    ) -> Result<Computations<'a>, MatrixError> {
+        let point = r.position(self.time);
+        let eyev = -r.direction;
+        let mut normalv = match (self.u, self.v) {
+            (Some(u), Some(v)) => self.object.normal_at_uv(point, u, v)?,
+            _ => self.object.normal_at(point)?,
+        };
+        let inside = normalv.dot(eyev) < 0.0;
+        if inside {
+            normalv = -normalv;
+        }
+        let reflectv = r.direction.reflect(normalv);
+        let (n1, n2) = self.refractive_indices(xs);
+
+        Ok(Computations {
+            time: self.time,
+            object: self.object,
+            point,
+            over_point: point + normalv * EPSILON,
+            under_point: point - normalv * EPSILON,
+            eyev,
+            normalv,
+            reflectv,
+            inside,
+            n1,
+            n2,
+        })
+    }
+
+    // Walk the intersections tracking which objects the ray is currently
+    // inside of, up to and including this hit.
+    fn refractive_indices(&self, xs: &[Intersection<'a, Object>]) -> (f64, f64) {
+        let mut containers: Vec<&Object> = Vec::new();
+        let mut n1 = 1.0;
+        let mut n2 = 1.0;
+        for i in xs {
+            let is_hit = i.time == self.time && std::ptr::eq(i.object, self.object);
+            if is_hit {
+                n1 = containers
+                    .last()
+                    .map_or(1.0, |o| o.material().refractive_index);
+            }
+
+            match containers.iter().position(|o| std::ptr::eq(*o, i.object)) {
+                Some(index) => {
+                    containers.remove(index);
+                }
+                None => containers.push(i.object),
+            }
+
+            if is_hit {
+                n2 = containers
+                    .last()
+                    .map_or(1.0, |o| o.material().refractive_index);
+                break;
+            }
+        }
+        (n1, n2)
+    }
+}
+
+impl Computations<'_> {
+    /// Schlick's approximation of the Fresnel effect: the fraction of the
+    /// light reflected at the hit, the rest being refracted
+    pub fn schlick(&self) -> f64 {
+        let mut cos = self.eyev.dot(self.normalv);
+        if self.n1 > self.n2 {
+            let n_ratio = self.n1 / self.n2;
+            let sin2_t = n_ratio.powi(2) * (1.0 - cos.powi(2));
+            // Total internal reflection
+            if sin2_t > 1.0 {
+                return 1.0;
+            }
+            // Use the angle of the refracted ray instead
+            cos = (1.0 - sin2_t).sqrt();
+        }
+        let r0 = ((self.n1 - self.n2) / (self.n1 + self.n2)).powi(2);
+        r0 + (1.0 - r0) * (1.0 - cos).powi(5)
+    }
+}
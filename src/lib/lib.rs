@@ -1,13 +1,31 @@
+pub mod bounds;
+pub mod bvh;
+pub mod camera;
 pub mod canvas;
 pub mod color;
+pub mod cones;
+pub mod csg;
+pub mod cubes;
+pub mod cylinders;
+pub mod groups;
 pub mod intersections;
 pub mod lights;
 pub mod materials;
 pub mod matrix;
+pub mod noise;
+pub mod obj;
 pub mod objects;
+pub mod patterns;
+pub mod planes;
+pub mod ply;
+pub mod png;
 pub mod projectiles;
 pub mod rays;
+pub mod shapes;
 pub mod spheres;
+pub mod stl;
+pub mod textures;
+pub mod triangles;
 pub mod tuple;
 pub mod utils;
 pub mod world;
@@ -1,40 +1,63 @@
 use crate::{
     color::{Color, CommonColor},
     lights::PointLight,
+    matrix::MatrixError,
+    objects::Object,
+    patterns::Pattern,
     tuple::{Point, Vector},
 };
 
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct Material {
     pub color: Color,
+    /// Used instead of `color` when set
+    pub pattern: Option<Pattern>,
     pub ambient: f64,
     pub diffuse: f64,
     pub specular: f64,
     pub shininess: f64,
+    /// 0 for a matte surface up to 1 for a perfect mirror
+    pub reflective: f64,
+    /// 0 for an opaque surface up to 1 for a fully transparent one
+    pub transparency: f64,
+    /// 1 for a vacuum, higher for denser materials. Hit computations read
+    /// it to find `n1` and `n2` on either side of each intersection.
+    pub refractive_index: f64,
 }
 
 impl Default for Material {
     fn default() -> Self {
         Material {
             color: Color::new(1.0, 1.0, 1.0),
+            pattern: None,
             ambient: 0.1,
             diffuse: 0.9,
             specular: 0.9,
             shininess: 200.0,
+            reflective: 0.0,
+            transparency: 0.0,
+            refractive_index: 1.0,
         }
     }
 }
 
 impl Material {
+    /// Phong shading of the object at a world space point
     pub fn lighting(
         &self,
+        object: &Object,
         light: PointLight,
         point: Point,
         eyev: Vector,
         normalv: Vector,
-    ) -> Color {
+        in_shadow: bool,
+    ) -> Result<Color, MatrixError> {
+        let color = match &self.pattern {
+            Some(pattern) => pattern.pattern_at_object(object, point)?,
+            None => self.color,
+        };
         // Combine the surface color with the light's color/intensity
-        let effective_color = self.color * light.intensity;
+        let effective_color = color * light.intensity;
 
         // Find the direction to the light source
         let lightv = (light.position - point).normalize();
@@ -42,6 +65,11 @@ impl Material {
         // Compute the ambient contribution
         let ambient = effective_color * self.ambient;
 
+        // Only ambient light reaches a point hidden from the light source
+        if in_shadow {
+            return Ok(ambient);
+        }
+
         let light_dot_normal = lightv.dot(normalv);
         let mut diffuse = CommonColor::Black.value();
         let mut specular = CommonColor::Black.value();
@@ -56,7 +84,7 @@ impl Material {
             }
         }
 
-        ambient + diffuse + specular
+        Ok(ambient + diffuse + specular)
     }
 }
 
@@ -65,11 +93,21 @@ mod tests {
     use crate::{
         color::Color,
         lights::PointLight,
+        spheres::Sphere,
         tuple::{IsTuple, Point, Vector},
     };
 
     use super::*;
 
+    #[test]
+    fn test_default() {
+        let m = Material::default();
+        assert_eq!(None, m.pattern);
+        assert_eq!(0.0, m.reflective);
+        assert_eq!(0.0, m.transparency);
+        assert_eq!(1.0, m.refractive_index);
+    }
+
     #[test]
     // Eye between light and suface
     fn test_lighting_1() {
@@ -78,9 +116,11 @@ mod tests {
         let light = PointLight::new(Point::new(0.0, 0.0, -10.0), Color::new(1.0, 1.0, 1.0));
         let position = Point::new(0.0, 0.0, 0.0);
         let m = Material::default();
+        let object = Object::from(Sphere::default());
         assert_eq!(
             Color::new(1.9, 1.9, 1.9),
-            m.lighting(light, position, eyev, normalv)
+            m.lighting(&object, light, position, eyev, normalv, false)
+                .unwrap()
         );
     }
 
@@ -92,9 +132,11 @@ mod tests {
         let light = PointLight::new(Point::new(0.0, 0.0, -10.0), Color::new(1.0, 1.0, 1.0));
         let position = Point::new(0.0, 0.0, 0.0);
         let m = Material::default();
+        let object = Object::from(Sphere::default());
         assert_eq!(
             Color::new(1.0, 1.0, 1.0),
-            m.lighting(light, position, eyev, normalv)
+            m.lighting(&object, light, position, eyev, normalv, false)
+                .unwrap()
         );
     }
 
@@ -106,9 +148,11 @@ mod tests {
         let light = PointLight::new(Point::new(0.0, 10.0, -10.0), Color::new(1.0, 1.0, 1.0));
         let position = Point::new(0.0, 0.0, 0.0);
         let m = Material::default();
+        let object = Object::from(Sphere::default());
         assert_eq!(
             Color::new(0.7364, 0.7364, 0.7364),
-            m.lighting(light, position, eyev, normalv)
+            m.lighting(&object, light, position, eyev, normalv, false)
+                .unwrap()
                 .limit_precision(4)
         );
     }
@@ -121,9 +165,11 @@ mod tests {
         let light = PointLight::new(Point::new(0.0, 10.0, -10.0), Color::new(1.0, 1.0, 1.0));
         let position = Point::new(0.0, 0.0, 0.0);
         let m = Material::default();
+        let object = Object::from(Sphere::default());
         assert_eq!(
             Color::new(1.6364, 1.6364, 1.6364),
-            m.lighting(light, position, eyev, normalv)
+            m.lighting(&object, light, position, eyev, normalv, false)
+                .unwrap()
                 .limit_precision(4)
         );
     }
@@ -136,9 +182,67 @@ mod tests {
         let light = PointLight::new(Point::new(0.0, 0.0, 10.0), Color::new(1.0, 1.0, 1.0));
         let position = Point::new(0.0, 0.0, 0.0);
         let m = Material::default();
+        let object = Object::from(Sphere::default());
         assert_eq!(
             Color::new(0.1, 0.1, 0.1),
-            m.lighting(light, position, eyev, normalv)
+            m.lighting(&object, light, position, eyev, normalv, false)
+                .unwrap()
         );
     }
+
+    #[test]
+    // Surface in shadow
+    fn test_lighting_in_shadow() {
+        let eyev = Vector::new(0.0, 0.0, -1.0);
+        let normalv = Vector::new(0.0, 0.0, -1.0);
+        let light = PointLight::new(Point::new(0.0, 0.0, -10.0), Color::new(1.0, 1.0, 1.0));
+        let position = Point::new(0.0, 0.0, 0.0);
+        let m = Material::default();
+        let object = Object::from(Sphere::default());
+        assert_eq!(
+            Color::new(0.1, 0.1, 0.1),
+            m.lighting(&object, light, position, eyev, normalv, true)
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn test_lighting_pattern() {
+        let m = Material {
+            pattern: Some(Pattern::stripe(
+                Color::new(1.0, 1.0, 1.0),
+                Color::new(0.0, 0.0, 0.0),
+            )),
+            ambient: 1.0,
+            diffuse: 0.0,
+            specular: 0.0,
+            ..Material::default()
+        };
+        let object = Object::from(Sphere::default());
+        let eyev = Vector::new(0.0, 0.0, -1.0);
+        let normalv = Vector::new(0.0, 0.0, -1.0);
+        let light = PointLight::new(Point::new(0.0, 0.0, -10.0), Color::new(1.0, 1.0, 1.0));
+        let c1 = m
+            .lighting(
+                &object,
+                light,
+                Point::new(0.9, 0.0, 0.0),
+                eyev,
+                normalv,
+                false,
+            )
+            .unwrap();
+        let c2 = m
+            .lighting(
+                &object,
+                light,
+                Point::new(1.1, 0.0, 0.0),
+                eyev,
+                normalv,
+                false,
+            )
+            .unwrap();
+        assert_eq!(Color::new(1.0, 1.0, 1.0), c1);
+        assert_eq!(Color::new(0.0, 0.0, 0.0), c2);
+    }
 }
@@ -1,7 +1,4 @@
-use std::{
-    ops::{Add, AddAssign, Div, Index, IndexMut, Mul, Neg, Sub},
-    usize,
-};
+use std::ops::{Add, AddAssign, Div, Index, IndexMut, Mul, Neg, Sub};
 
 use num::{Float, Integer, NumCast, One, Zero};
 
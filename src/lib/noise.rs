@@ -0,0 +1,385 @@
+use std::sync::Arc;
+
+use crate::tuple::{IsTuple, Point, Vector};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NoiseKind {
+    /// Ken Perlin's improved gradient noise
+    Perlin,
+    /// Simplex noise, smoother and with fewer axis aligned artifacts
+    Simplex,
+}
+
+/// How noise octaves are combined
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NoiseFunction {
+    /// A single octave, in [-1, 1]
+    Plain,
+    /// Fractal Brownian motion over the given number of octaves, in [-1, 1]
+    Fbm(usize),
+    /// Sum of the absolute value of each octave, in [0, 1]
+    Turbulence(usize),
+}
+
+// Edge midpoints of a cube, used as simplex gradients
+const GRAD3: [[f64; 3]; 12] = [
+    [1.0, 1.0, 0.0],
+    [-1.0, 1.0, 0.0],
+    [1.0, -1.0, 0.0],
+    [-1.0, -1.0, 0.0],
+    [1.0, 0.0, 1.0],
+    [-1.0, 0.0, 1.0],
+    [1.0, 0.0, -1.0],
+    [-1.0, 0.0, -1.0],
+    [0.0, 1.0, 1.0],
+    [0.0, -1.0, 1.0],
+    [0.0, 1.0, -1.0],
+    [0.0, -1.0, -1.0],
+];
+
+/// Deterministic 3D noise. The same kind and seed always give the same values.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Noise {
+    kind: NoiseKind,
+    seed: u64,
+    // Shuffled 0..256, repeated twice to avoid wrapping indices. Shared
+    // between clones, like texture images.
+    perm: Arc<[u8; 512]>,
+}
+
+impl Noise {
+    pub fn new(kind: NoiseKind, seed: u64) -> Self {
+        let mut perm = [0; 512];
+        for (i, entry) in perm[..256].iter_mut().enumerate() {
+            *entry = i as u8;
+        }
+        let mut state = seed;
+        for i in (1..256).rev() {
+            let j = (splitmix64(&mut state) % (i as u64 + 1)) as usize;
+            perm.swap(i, j);
+        }
+        perm.copy_within(..256, 256);
+        Noise {
+            kind,
+            seed,
+            perm: Arc::new(perm),
+        }
+    }
+
+    pub fn perlin(seed: u64) -> Self {
+        Noise::new(NoiseKind::Perlin, seed)
+    }
+
+    pub fn simplex(seed: u64) -> Self {
+        Noise::new(NoiseKind::Simplex, seed)
+    }
+
+    pub fn kind(&self) -> NoiseKind {
+        self.kind
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Noise at a point, roughly in [-1, 1]
+    pub fn noise(&self, p: Point) -> f64 {
+        self.noise_xyz(p.0.x, p.0.y, p.0.z)
+    }
+
+    /// Fractal Brownian motion: octaves of doubling frequency and halving
+    /// amplitude, normalized back to [-1, 1]
+    pub fn fbm(&self, p: Point, octaves: usize) -> f64 {
+        self.octaves(p, octaves, |n| n)
+    }
+
+    /// Like `fbm` but summing the absolute value of every octave, which gives
+    /// sharp creases where the noise crosses zero. In [0, 1].
+    pub fn turbulence(&self, p: Point, octaves: usize) -> f64 {
+        self.octaves(p, octaves, f64::abs)
+    }
+
+    pub fn eval(&self, p: Point, function: NoiseFunction) -> f64 {
+        match function {
+            NoiseFunction::Plain => self.noise(p),
+            NoiseFunction::Fbm(octaves) => self.fbm(p, octaves),
+            NoiseFunction::Turbulence(octaves) => self.turbulence(p, octaves),
+        }
+    }
+
+    /// Point moved by up to `scale` along each axis. Each axis samples the
+    /// noise at a different offset so that they vary independently.
+    pub fn perturb(&self, p: Point, scale: f64) -> Point {
+        let (x, y, z) = (p.0.x, p.0.y, p.0.z);
+        let offset = Vector::new(
+            self.noise_xyz(x, y, z),
+            self.noise_xyz(x + 31.416, y + 47.853, z + 12.793),
+            self.noise_xyz(x + 73.156, y + 5.237, z + 91.682),
+        );
+        p + offset * scale
+    }
+
+    fn octaves(&self, p: Point, octaves: usize, f: impl Fn(f64) -> f64) -> f64 {
+        let (mut sum, mut total, mut amplitude, mut frequency) = (0.0, 0.0, 1.0, 1.0);
+        for _ in 0..octaves.max(1) {
+            let n = self.noise_xyz(p.0.x * frequency, p.0.y * frequency, p.0.z * frequency);
+            sum += f(n) * amplitude;
+            total += amplitude;
+            amplitude *= 0.5;
+            frequency *= 2.0;
+        }
+        sum / total
+    }
+
+    fn noise_xyz(&self, x: f64, y: f64, z: f64) -> f64 {
+        match self.kind {
+            NoiseKind::Perlin => self.perlin_xyz(x, y, z),
+            NoiseKind::Simplex => self.simplex_xyz(x, y, z),
+        }
+    }
+
+    fn perlin_xyz(&self, x: f64, y: f64, z: f64) -> f64 {
+        let p = |i: usize| self.perm[i] as usize;
+        // Unit cube containing the point, and the position within it
+        let (xi, yi, zi) = (lattice(x), lattice(y), lattice(z));
+        let (x, y, z) = (x - x.floor(), y - y.floor(), z - z.floor());
+        let (u, v, w) = (fade(x), fade(y), fade(z));
+
+        // Hash the coordinates of the eight cube corners
+        let a = p(xi) + yi;
+        let (aa, ab) = (p(a) + zi, p(a + 1) + zi);
+        let b = p(xi + 1) + yi;
+        let (ba, bb) = (p(b) + zi, p(b + 1) + zi);
+
+        lerp(
+            w,
+            lerp(
+                v,
+                lerp(u, grad(p(aa), x, y, z), grad(p(ba), x - 1.0, y, z)),
+                lerp(
+                    u,
+                    grad(p(ab), x, y - 1.0, z),
+                    grad(p(bb), x - 1.0, y - 1.0, z),
+                ),
+            ),
+            lerp(
+                v,
+                lerp(
+                    u,
+                    grad(p(aa + 1), x, y, z - 1.0),
+                    grad(p(ba + 1), x - 1.0, y, z - 1.0),
+                ),
+                lerp(
+                    u,
+                    grad(p(ab + 1), x, y - 1.0, z - 1.0),
+                    grad(p(bb + 1), x - 1.0, y - 1.0, z - 1.0),
+                ),
+            ),
+        )
+    }
+
+    fn simplex_xyz(&self, x: f64, y: f64, z: f64) -> f64 {
+        const F3: f64 = 1.0 / 3.0;
+        const G3: f64 = 1.0 / 6.0;
+        let p = |i: usize| self.perm[i] as usize;
+
+        // Skew the input space to find the simplex cell
+        let s = (x + y + z) * F3;
+        let (i, j, k) = ((x + s).floor(), (y + s).floor(), (z + s).floor());
+        let t = (i + j + k) * G3;
+        let (x0, y0, z0) = (x - (i - t), y - (j - t), z - (k - t));
+
+        // Offsets of the second and third corners of the simplex
+        let ((i1, j1, k1), (i2, j2, k2)) = if x0 >= y0 {
+            if y0 >= z0 {
+                ((1, 0, 0), (1, 1, 0))
+            } else if x0 >= z0 {
+                ((1, 0, 0), (1, 0, 1))
+            } else {
+                ((0, 0, 1), (1, 0, 1))
+            }
+        } else if y0 < z0 {
+            ((0, 0, 1), (0, 1, 1))
+        } else if x0 < z0 {
+            ((0, 1, 0), (0, 1, 1))
+        } else {
+            ((0, 1, 0), (1, 1, 0))
+        };
+
+        let corners = [
+            (0, 0, 0, x0, y0, z0),
+            (
+                i1,
+                j1,
+                k1,
+                x0 - i1 as f64 + G3,
+                y0 - j1 as f64 + G3,
+                z0 - k1 as f64 + G3,
+            ),
+            (
+                i2,
+                j2,
+                k2,
+                x0 - i2 as f64 + 2.0 * G3,
+                y0 - j2 as f64 + 2.0 * G3,
+                z0 - k2 as f64 + 2.0 * G3,
+            ),
+            (
+                1,
+                1,
+                1,
+                x0 - 1.0 + 3.0 * G3,
+                y0 - 1.0 + 3.0 * G3,
+                z0 - 1.0 + 3.0 * G3,
+            ),
+        ];
+
+        let (ii, jj, kk) = (lattice(i), lattice(j), lattice(k));
+        let mut n = 0.0;
+        for (ci, cj, ck, cx, cy, cz) in corners {
+            let t = 0.6 - cx * cx - cy * cy - cz * cz;
+            if t > 0.0 {
+                let g = GRAD3[p(ii + ci + p(jj + cj + p(kk + ck))) % 12];
+                n += t.powi(4) * (g[0] * cx + g[1] * cy + g[2] * cz);
+            }
+        }
+        // Scale the result to fit [-1, 1]
+        32.0 * n
+    }
+}
+
+// Lattice coordinate wrapped to the permutation table
+fn lattice(x: f64) -> usize {
+    (x.floor() as i64).rem_euclid(256) as usize
+}
+
+fn fade(t: f64) -> f64 {
+    t * t * t * (t * (t * 6.0 - 15.0) + 10.0)
+}
+
+fn lerp(t: f64, a: f64, b: f64) -> f64 {
+    a + t * (b - a)
+}
+
+// Dot product with one of 12 gradient directions picked by the hash
+fn grad(hash: usize, x: f64, y: f64, z: f64) -> f64 {
+    let h = hash & 15;
+    let u = if h < 8 { x } else { y };
+    let v = if h < 4 {
+        y
+    } else if h == 12 || h == 14 {
+        x
+    } else {
+        z
+    };
+    (if h & 1 == 0 { u } else { -u }) + (if h & 2 == 0 { v } else { -v })
+}
+
+fn splitmix64(state: &mut u64) -> u64 {
+    *state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    let mut z = *state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Points scattered over a few lattice cells, avoiding the lattice itself
+    fn sample_points() -> Vec<Point> {
+        let mut points = Vec::new();
+        for i in 0..1000 {
+            let t = i as f64;
+            points.push(Point::new(
+                (t * 0.137).sin() * 7.3,
+                (t * 0.071).cos() * 5.1,
+                t * 0.0193 - 9.0,
+            ));
+        }
+        points
+    }
+
+    #[test]
+    fn test_deterministic() {
+        for kind in [NoiseKind::Perlin, NoiseKind::Simplex] {
+            let (n1, n2) = (Noise::new(kind, 42), Noise::new(kind, 42));
+            assert_eq!(n1, n2);
+            for p in sample_points() {
+                assert_eq!(n1.noise(p), n2.noise(p));
+            }
+        }
+    }
+
+    #[test]
+    fn test_seeds_differ() {
+        for kind in [NoiseKind::Perlin, NoiseKind::Simplex] {
+            let (n1, n2) = (Noise::new(kind, 1), Noise::new(kind, 2));
+            assert!(sample_points().iter().any(|&p| n1.noise(p) != n2.noise(p)));
+        }
+    }
+
+    #[test]
+    fn test_clones_share_table() {
+        let n = Noise::perlin(3);
+        assert!(Arc::ptr_eq(&n.perm, &n.clone().perm));
+    }
+
+    #[test]
+    fn test_range() {
+        for kind in [NoiseKind::Perlin, NoiseKind::Simplex] {
+            let n = Noise::new(kind, 7);
+            let values: Vec<f64> = sample_points().iter().map(|&p| n.noise(p)).collect();
+            assert!(values.iter().all(|v| (-1.0..=1.0).contains(v)));
+            // Not flat
+            let min = values.iter().cloned().fold(f64::INFINITY, f64::min);
+            let max = values.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+            assert!(max - min > 0.5);
+        }
+    }
+
+    #[test]
+    fn test_perlin_zero_on_lattice() {
+        let n = Noise::perlin(3);
+        for p in [[0.0, 0.0, 0.0], [1.0, 2.0, 3.0], [-4.0, 7.0, -1.0]] {
+            assert_eq!(0.0, n.noise(Point::from(p)));
+        }
+    }
+
+    #[test]
+    fn test_continuous() {
+        for kind in [NoiseKind::Perlin, NoiseKind::Simplex] {
+            let n = Noise::new(kind, 11);
+            for p in sample_points() {
+                let q = p + Vector::new(0.0001, 0.0001, 0.0001);
+                assert!((n.noise(p) - n.noise(q)).abs() < 0.01);
+            }
+        }
+    }
+
+    #[test]
+    fn test_fbm_and_turbulence() {
+        let n = Noise::simplex(5);
+        for p in sample_points() {
+            assert_eq!(n.noise(p), n.fbm(p, 1));
+            assert_eq!(n.noise(p).abs(), n.turbulence(p, 1));
+            assert!((-1.0..=1.0).contains(&n.fbm(p, 4)));
+            assert!((0.0..=1.0).contains(&n.turbulence(p, 4)));
+            assert_eq!(n.fbm(p, 4), n.eval(p, NoiseFunction::Fbm(4)));
+            assert_eq!(n.turbulence(p, 4), n.eval(p, NoiseFunction::Turbulence(4)));
+        }
+    }
+
+    #[test]
+    fn test_perturb() {
+        let n = Noise::perlin(9);
+        for p in sample_points() {
+            assert_eq!(p, n.perturb(p, 0.0));
+            let q = n.perturb(p, 0.25);
+            let d = q - p;
+            assert!(d.0.x.abs() <= 0.25 && d.0.y.abs() <= 0.25 && d.0.z.abs() <= 0.25);
+        }
+        assert!(sample_points().iter().any(|&p| n.perturb(p, 0.25) != p));
+    }
+}
@@ -0,0 +1,406 @@
+use thiserror::Error;
+
+use crate::{
+    groups::Group,
+    objects::Object,
+    triangles::{SmoothTriangle, Triangle},
+    tuple::{IsTuple, Point, Vector},
+};
+
+#[derive(Error, Debug, PartialEq)]
+pub enum ObjError {
+    #[error("Line {line}: {reason}")]
+    Malformed { line: usize, reason: String },
+    #[error("Line {line}: index {index} is out of range")]
+    IndexOutOfRange { line: usize, index: i64 },
+}
+
+/// Wavefront OBJ mesh.
+/// Faces are fan triangulated, faces with a normal at every vertex become
+/// smooth triangles. Faces after a `g` or `o` statement go to the named
+/// group, the rest to the default group.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ObjFile {
+    vertices: Vec<Point>,
+    normals: Vec<Vector>,
+    texture_coords: Vec<(f64, f64)>,
+    default_group: Group,
+    groups: Vec<(String, Group)>,
+    ignored: Vec<usize>,
+}
+
+impl ObjFile {
+    pub fn parse(content: &str) -> Result<Self, ObjError> {
+        let mut obj = ObjFile::default();
+        // Index into `groups` that faces are currently added to
+        let mut current: Option<usize> = None;
+
+        for (i, line) in content.lines().enumerate() {
+            let line_num = i + 1;
+            let mut tokens = line.split_whitespace();
+            let keyword = match tokens.next() {
+                Some(k) if !k.starts_with('#') => k,
+                // Blank lines and comments
+                _ => continue,
+            };
+            let args: Vec<&str> = tokens.collect();
+
+            match keyword {
+                "v" => {
+                    let [x, y, z] = parse_floats(&args, line_num, "vertex")?;
+                    obj.vertices.push(Point::new(x, y, z));
+                }
+                "vn" => {
+                    let [x, y, z] = parse_floats(&args, line_num, "vertex normal")?;
+                    obj.normals.push(Vector::new(x, y, z));
+                }
+                "vt" => {
+                    // The v coordinate is optional and defaults to 0
+                    let u = parse_float(args.first(), line_num, "texture coordinate")?;
+                    let v = match args.get(1) {
+                        Some(_) => parse_float(args.get(1), line_num, "texture coordinate")?,
+                        None => 0.0,
+                    };
+                    obj.texture_coords.push((u, v));
+                }
+                "f" => {
+                    let triangles = obj.parse_face(&args, line_num)?;
+                    let group = match current {
+                        Some(idx) => &mut obj.groups[idx].1,
+                        None => &mut obj.default_group,
+                    };
+                    for t in triangles {
+                        group.add_child(t);
+                    }
+                }
+                "g" | "o" => {
+                    let name = args.join(" ");
+                    current = Some(match obj.groups.iter().position(|(n, _)| *n == name) {
+                        Some(idx) => idx,
+                        None => {
+                            obj.groups.push((name, Group::new()));
+                            obj.groups.len() - 1
+                        }
+                    });
+                }
+                _ => obj.ignored.push(line_num),
+            }
+        }
+
+        Ok(obj)
+    }
+
+    fn parse_face(&self, args: &[&str], line: usize) -> Result<Vec<Object>, ObjError> {
+        if args.len() < 3 {
+            return Err(ObjError::Malformed {
+                line,
+                reason: format!("face needs at least 3 vertices, got {}", args.len()),
+            });
+        }
+
+        let mut points = Vec::with_capacity(args.len());
+        let mut normals = Vec::with_capacity(args.len());
+        for arg in args {
+            // Each vertex is one of v, v/vt, v//vn or v/vt/vn
+            let mut refs = arg.split('/');
+            let v = refs.next().unwrap_or_default();
+            let vt = refs.next().unwrap_or_default();
+            let vn = refs.next().unwrap_or_default();
+            points.push(self.vertices[resolve_index(v, self.vertices.len(), line)?]);
+            if !vt.is_empty() {
+                resolve_index(vt, self.texture_coords.len(), line)?;
+            }
+            if !vn.is_empty() {
+                normals.push(self.normals[resolve_index(vn, self.normals.len(), line)?]);
+            }
+        }
+
+        // Fan out from the first vertex
+        let smooth = normals.len() == points.len();
+        let triangles = (1..points.len() - 1)
+            .map(|i| {
+                let (p1, p2, p3) = (points[0], points[i], points[i + 1]);
+                if smooth {
+                    let (n1, n2, n3) = (normals[0], normals[i], normals[i + 1]);
+                    SmoothTriangle::new(p1, p2, p3, n1, n2, n3).into()
+                } else {
+                    Triangle::new(p1, p2, p3).into()
+                }
+            })
+            .collect();
+        Ok(triangles)
+    }
+
+    /// Vertex by its 1-based OBJ index
+    pub fn vertex(&self, index: usize) -> Option<Point> {
+        self.vertices.get(index.checked_sub(1)?).copied()
+    }
+
+    pub fn vertices(&self) -> &[Point] {
+        &self.vertices
+    }
+
+    pub fn normals(&self) -> &[Vector] {
+        &self.normals
+    }
+
+    pub fn texture_coords(&self) -> &[(f64, f64)] {
+        &self.texture_coords
+    }
+
+    /// Faces that appear before any named group
+    pub fn default_group(&self) -> &Group {
+        &self.default_group
+    }
+
+    pub fn group(&self, name: &str) -> Option<&Group> {
+        self.groups.iter().find(|(n, _)| n == name).map(|(_, g)| g)
+    }
+
+    /// Line numbers of statements that aren't supported, e.g. `mtllib`
+    pub fn ignored(&self) -> &[usize] {
+        &self.ignored
+    }
+
+    /// Single group holding the default group's triangles and every
+    /// non-empty named group
+    pub fn into_group(self) -> Group {
+        let mut mesh = Group::new();
+        for child in self.default_group.into_children() {
+            mesh.add_child(child);
+        }
+        for (_, g) in self.groups {
+            if !g.is_empty() {
+                mesh.add_child(g);
+            }
+        }
+        mesh
+    }
+}
+
+impl From<ObjFile> for Object {
+    fn from(obj: ObjFile) -> Self {
+        obj.into_group().into()
+    }
+}
+
+fn parse_float(arg: Option<&&str>, line: usize, what: &str) -> Result<f64, ObjError> {
+    let arg = arg.ok_or_else(|| ObjError::Malformed {
+        line,
+        reason: format!("missing value for {}", what),
+    })?;
+    arg.parse().map_err(|_| ObjError::Malformed {
+        line,
+        reason: format!("invalid number {:?} for {}", arg, what),
+    })
+}
+
+fn parse_floats(args: &[&str], line: usize, what: &str) -> Result<[f64; 3], ObjError> {
+    Ok([
+        parse_float(args.first(), line, what)?,
+        parse_float(args.get(1), line, what)?,
+        parse_float(args.get(2), line, what)?,
+    ])
+}
+
+/// Convert a 1-based, or negative relative, OBJ index into a 0-based one
+fn resolve_index(arg: &str, len: usize, line: usize) -> Result<usize, ObjError> {
+    let index: i64 = arg.parse().map_err(|_| ObjError::Malformed {
+        line,
+        reason: format!("invalid index {:?}", arg),
+    })?;
+    let resolved = if index < 0 {
+        len as i64 + index
+    } else {
+        index - 1
+    };
+    if resolved < 0 || resolved >= len as i64 {
+        return Err(ObjError::IndexOutOfRange { line, index });
+    }
+    Ok(resolved as usize)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn triangle(obj: &Object) -> &Triangle {
+        match obj {
+            Object::Triangle(t) => t,
+            _ => panic!("Expected a triangle"),
+        }
+    }
+
+    #[test]
+    fn test_ignored_lines() {
+        let content = "There was a young lady named Bright\n\
+                       who traveled much faster than light.\n\
+                       \n\
+                       # She set out one day\n\
+                       mtllib in a relative way\n";
+        let obj = ObjFile::parse(content).unwrap();
+        assert_eq!(&[1, 2, 5], obj.ignored());
+        assert!(obj.default_group().is_empty());
+    }
+
+    #[test]
+    fn test_vertices() {
+        let content = "v -1 1 0\nv -1.0000 0.5000 0.0000\nv 1 0 0\nv 1 1 0\n";
+        let obj = ObjFile::parse(content).unwrap();
+        assert_eq!(Some(Point::new(-1.0, 1.0, 0.0)), obj.vertex(1));
+        assert_eq!(Some(Point::new(-1.0, 0.5, 0.0)), obj.vertex(2));
+        assert_eq!(Some(Point::new(1.0, 0.0, 0.0)), obj.vertex(3));
+        assert_eq!(Some(Point::new(1.0, 1.0, 0.0)), obj.vertex(4));
+        assert_eq!(None, obj.vertex(0));
+        assert_eq!(None, obj.vertex(5));
+    }
+
+    #[test]
+    fn test_faces() {
+        let content = "v -1 1 0\nv -1 0 0\nv 1 0 0\nv 1 1 0\n\nf 1 2 3\nf 1 3 4\n";
+        let obj = ObjFile::parse(content).unwrap();
+        let children = obj.default_group().children();
+        assert_eq!(2, children.len());
+        let (t1, t2) = (triangle(&children[0]), triangle(&children[1]));
+        assert_eq!(obj.vertex(1).unwrap(), t1.p1());
+        assert_eq!(obj.vertex(2).unwrap(), t1.p2());
+        assert_eq!(obj.vertex(3).unwrap(), t1.p3());
+        assert_eq!(obj.vertex(1).unwrap(), t2.p1());
+        assert_eq!(obj.vertex(3).unwrap(), t2.p2());
+        assert_eq!(obj.vertex(4).unwrap(), t2.p3());
+    }
+
+    #[test]
+    fn test_polygon_fan() {
+        let content = "v -1 1 0\nv -1 0 0\nv 1 0 0\nv 1 1 0\nv 0 2 0\n\nf 1 2 3 4 5\n";
+        let obj = ObjFile::parse(content).unwrap();
+        let children = obj.default_group().children();
+        assert_eq!(3, children.len());
+        for (i, child) in children.iter().enumerate() {
+            let t = triangle(child);
+            assert_eq!(obj.vertex(1).unwrap(), t.p1());
+            assert_eq!(obj.vertex(i + 2).unwrap(), t.p2());
+            assert_eq!(obj.vertex(i + 3).unwrap(), t.p3());
+        }
+    }
+
+    #[test]
+    fn test_named_groups() {
+        let content = "v -1 1 0\nv -1 0 0\nv 1 0 0\nv 1 1 0\n\
+                       g FirstGroup\nf 1 2 3\no SecondGroup\nf 1 3 4\n";
+        let obj = ObjFile::parse(content).unwrap();
+        assert!(obj.default_group().is_empty());
+        let t1 = triangle(&obj.group("FirstGroup").unwrap().children()[0]);
+        let t2 = triangle(&obj.group("SecondGroup").unwrap().children()[0]);
+        assert_eq!(obj.vertex(2).unwrap(), t1.p2());
+        assert_eq!(obj.vertex(4).unwrap(), t2.p3());
+        assert!(obj.group("ThirdGroup").is_none());
+    }
+
+    #[test]
+    fn test_into_group() {
+        let content = "v -1 1 0\nv -1 0 0\nv 1 0 0\nv 1 1 0\n\
+                       f 1 2 3\ng FirstGroup\nf 1 2 3\ng SecondGroup\nf 1 3 4\ng Empty\n";
+        let g = ObjFile::parse(content).unwrap().into_group();
+        assert_eq!(3, g.children().len());
+        assert!(matches!(g.children()[0], Object::Triangle(_)));
+        assert!(matches!(g.children()[1], Object::Group(_)));
+        assert!(matches!(g.children()[2], Object::Group(_)));
+    }
+
+    #[test]
+    fn test_normals_and_texture_coords() {
+        let content = "vn 0 0 1\nvn 0.707 0 -0.707\nvn 1 2 3\nvt 0.5 0.25\nvt 0.75\n";
+        let obj = ObjFile::parse(content).unwrap();
+        assert_eq!(
+            &[
+                Vector::new(0.0, 0.0, 1.0),
+                Vector::new(0.707, 0.0, -0.707),
+                Vector::new(1.0, 2.0, 3.0),
+            ],
+            obj.normals()
+        );
+        assert_eq!(&[(0.5, 0.25), (0.75, 0.0)], obj.texture_coords());
+    }
+
+    #[test]
+    fn test_faces_with_normals() {
+        let content = "v 0 1 0\nv -1 0 0\nv 1 0 0\n\
+                       vn -1 0 0\nvn 1 0 0\nvn 0 1 0\nvt 0 0\n\
+                       f 1//3 2//1 3//2\nf 1/1/3 2/1/1 3/1/2\n";
+        let obj = ObjFile::parse(content).unwrap();
+        let children = obj.default_group().children();
+        assert_eq!(children[0], children[1]);
+        match &children[0] {
+            Object::SmoothTriangle(t) => {
+                assert_eq!(obj.vertex(1).unwrap(), t.p1());
+                assert_eq!(obj.vertex(3).unwrap(), t.p3());
+                assert_eq!(obj.normals()[2], t.n1());
+                assert_eq!(obj.normals()[0], t.n2());
+                assert_eq!(obj.normals()[1], t.n3());
+            }
+            _ => panic!("Expected a smooth triangle"),
+        }
+    }
+
+    #[test]
+    fn test_negative_indices() {
+        let content = "v -1 1 0\nv -1 0 0\nv 1 0 0\nf -3 -2 -1\n";
+        let obj = ObjFile::parse(content).unwrap();
+        let t = triangle(&obj.default_group().children()[0]);
+        assert_eq!(obj.vertex(1).unwrap(), t.p1());
+        assert_eq!(obj.vertex(3).unwrap(), t.p3());
+    }
+
+    #[test]
+    fn test_malformed() {
+        let cases = [
+            "v 1 two 3\n",
+            "v 1 2\n",
+            "vn 1 2\n",
+            "vt\n",
+            "vt abc\n",
+            "v 1 2 3\nv 1 2 3\nf 1 2\n",
+            "v 1 2 3\nv 1 2 3\nv 1 2 3\nf 1 2 x\n",
+            "v 1 2 3\nv 1 2 3\nv 1 2 3\nf 1/zzz 2 3\n",
+        ];
+        for content in cases {
+            let line = content.lines().count();
+            match ObjFile::parse(content) {
+                Err(ObjError::Malformed { line: l, .. }) => assert_eq!(line, l),
+                other => panic!("Expected a malformed error, got {:?}", other),
+            }
+        }
+    }
+
+    #[test]
+    fn test_index_out_of_range() {
+        let content = "v 1 2 3\nv 1 2 3\nv 1 2 3\nf 1 2 4\n";
+        assert_eq!(
+            Err(ObjError::IndexOutOfRange { line: 4, index: 4 }),
+            ObjFile::parse(content)
+        );
+        let content = "v 1 2 3\nv 1 2 3\nv 1 2 3\nf 0 1 2\n";
+        assert_eq!(
+            Err(ObjError::IndexOutOfRange { line: 4, index: 0 }),
+            ObjFile::parse(content)
+        );
+        let content = "v 1 2 3\nv 1 2 3\nv 1 2 3\nf 1//1 2//1 3//1\n";
+        assert_eq!(
+            Err(ObjError::IndexOutOfRange { line: 4, index: 1 }),
+            ObjFile::parse(content)
+        );
+        let content = "v 1 2 3\nv 1 2 3\nv 1 2 3\nvt 0 0\nf 1/1 2/99 3/-7\n";
+        assert_eq!(
+            Err(ObjError::IndexOutOfRange { line: 5, index: 99 }),
+            ObjFile::parse(content)
+        );
+    }
+
+    #[test]
+    fn test_into_object() {
+        let content = "v -1 1 0\nv -1 0 0\nv 1 0 0\nf 1 2 3\n";
+        let obj = Object::from(ObjFile::parse(content).unwrap());
+        assert!(matches!(obj, Object::Group(_)));
+    }
+}
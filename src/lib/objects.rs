@@ -1,6 +1,246 @@
-use crate::spheres::Sphere;
+use std::sync::Arc;
+
+use crate::{
+    bounds::BoundingBox,
+    cones::Cone,
+    csg::Csg,
+    cubes::Cube,
+    cylinders::Cylinder,
+    groups::Group,
+    intersections::{Intersectable, Intersection},
+    materials::Material,
+    matrix::{Matrix, MatrixError},
+    planes::Plane,
+    rays::Ray,
+    shapes::{Placement, Shape},
+    spheres::Sphere,
+    triangles::{SmoothTriangle, Triangle},
+    tuple::{Point, Vector},
+};
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum Object {
     Sphere(Sphere),
+    Plane(Plane),
+    Cube(Cube),
+    Cylinder(Cylinder),
+    Cone(Cone),
+    Triangle(Triangle),
+    SmoothTriangle(SmoothTriangle),
+    Group(Group),
+    Csg(Csg),
+}
+
+impl Object {
+    // Primitive shape behind the object. Groups and CSGs are handled
+    // separately by every caller.
+    fn shape(&self) -> &dyn Shape {
+        match self {
+            Object::Sphere(s) => s,
+            Object::Plane(p) => p,
+            Object::Cube(c) => c,
+            Object::Cylinder(c) => c,
+            Object::Cone(c) => c,
+            Object::Triangle(t) => t,
+            Object::SmoothTriangle(t) => t,
+            Object::Group(_) | Object::Csg(_) => {
+                unreachable!("Groups and CSGs are not primitive shapes")
+            }
+        }
+    }
+
+    fn shape_mut(&mut self) -> &mut dyn Shape {
+        match self {
+            Object::Sphere(s) => s,
+            Object::Plane(p) => p,
+            Object::Cube(c) => c,
+            Object::Cylinder(c) => c,
+            Object::Cone(c) => c,
+            Object::Triangle(t) => t,
+            Object::SmoothTriangle(t) => t,
+            Object::Group(_) | Object::Csg(_) => {
+                unreachable!("Groups and CSGs are not primitive shapes")
+            }
+        }
+    }
+
+    pub fn material(&self) -> &Material {
+        match self {
+            Object::Group(g) => g.material(),
+            Object::Csg(c) => c.material(),
+            _ => self.shape().material(),
+        }
+    }
+
+    pub fn material_mut(&mut self) -> &mut Material {
+        match self {
+            Object::Group(g) => g.material_mut(),
+            Object::Csg(c) => c.material_mut(),
+            _ => self.shape_mut().material_mut(),
+        }
+    }
+
+    /// Apply the material to the object, and to everything within it for
+    /// groups and CSGs
+    pub fn set_material(&mut self, material: Material) {
+        match self {
+            Object::Group(g) => g.set_material(material),
+            Object::Csg(c) => c.set_material(material),
+            _ => *self.shape_mut().material_mut() = material,
+        }
+    }
+
+    pub fn transform(&self) -> &Matrix<f64> {
+        match self {
+            Object::Group(g) => g.transform(),
+            Object::Csg(c) => c.transform(),
+            _ => self.shape().transform(),
+        }
+    }
+
+    pub fn set_transform(&mut self, m: Matrix<f64>) {
+        match self {
+            Object::Group(g) => g.set_transform(m),
+            Object::Csg(c) => c.set_transform(m),
+            _ => self.shape_mut().set_transform(m),
+        }
+    }
+
+    pub(crate) fn set_parent(&mut self, parent: Option<Arc<Placement>>) {
+        match self {
+            Object::Group(g) => g.set_parent(parent),
+            Object::Csg(c) => c.set_parent(parent),
+            _ => self.shape_mut().placement_mut().set_parent(parent),
+        }
+    }
+
+    pub fn normal_at(&self, p: Point) -> Result<Vector, MatrixError> {
+        match self {
+            Object::Group(_) | Object::Csg(_) => Err(MatrixError::InvalidArgument(String::from(
+                "Groups and CSGs have no surface normal",
+            ))),
+            _ => self.shape().normal_at(p),
+        }
+    }
+
+    /// Convert a world space point to the object's own space
+    pub fn world_to_object(&self, p: Point) -> Result<Point, MatrixError> {
+        match self {
+            Object::Group(g) => g.placement().world_to_object(p),
+            Object::Csg(c) => c.placement().world_to_object(p),
+            _ => self.shape().placement().world_to_object(p),
+        }
+    }
+
+    /// Whether the object is, or contains, the other object
+    pub(crate) fn includes(&self, other: &Object) -> bool {
+        match self {
+            Object::Group(g) => g.children().iter().any(|c| c.includes(other)),
+            Object::Csg(c) => c.left().includes(other) || c.right().includes(other),
+            _ => std::ptr::eq(self, other),
+        }
+    }
+
+    /// Bounds in object space
+    pub fn bounds(&self) -> BoundingBox {
+        match self {
+            Object::Group(g) => g.bounds(),
+            Object::Csg(c) => c.bounds(),
+            _ => self.shape().bounds(),
+        }
+    }
+
+    /// Bounds once the object's own transform is applied
+    pub fn parent_space_bounds(&self) -> BoundingBox {
+        self.bounds().transform(self.transform())
+    }
+
+    /// Normal at a hit with barycentric `u`/`v`
+    pub fn normal_at_uv(&self, p: Point, u: f64, v: f64) -> Result<Vector, MatrixError> {
+        match self {
+            Object::Group(_) | Object::Csg(_) => self.normal_at(p),
+            _ => self.shape().normal_at_uv(p, u, v),
+        }
+    }
+}
+
+impl Intersectable for Object {
+    fn intersect(&self, r: Ray) -> Result<Vec<Intersection<'_, Object>>, MatrixError> {
+        Ok(match self {
+            Object::Sphere(s) => attribute(s.intersect(r)?, self),
+            Object::Plane(p) => attribute(p.intersect(r)?, self),
+            Object::Cube(c) => attribute(c.intersect(r)?, self),
+            Object::Cylinder(c) => attribute(c.intersect(r)?, self),
+            Object::Cone(c) => attribute(c.intersect(r)?, self),
+            Object::Triangle(t) => attribute(t.intersect(r)?, self),
+            Object::SmoothTriangle(t) => attribute(t.intersect(r)?, self),
+            Object::Group(g) => g.intersect(r)?,
+            Object::Csg(c) => c.intersect(r)?,
+        })
+    }
+}
+
+// Hits on a primitive are attributed to the object wrapping it
+fn attribute<'a, T>(
+    xs: Vec<Intersection<'_, T>>,
+    object: &'a Object,
+) -> Vec<Intersection<'a, Object>>
+where
+    T: Intersectable,
+{
+    xs.into_iter().map(|i| i.with_object(object)).collect()
+}
+
+impl From<Sphere> for Object {
+    fn from(s: Sphere) -> Self {
+        Object::Sphere(s)
+    }
+}
+
+impl From<Plane> for Object {
+    fn from(p: Plane) -> Self {
+        Object::Plane(p)
+    }
+}
+
+impl From<Cube> for Object {
+    fn from(c: Cube) -> Self {
+        Object::Cube(c)
+    }
+}
+
+impl From<Cylinder> for Object {
+    fn from(c: Cylinder) -> Self {
+        Object::Cylinder(c)
+    }
+}
+
+impl From<Cone> for Object {
+    fn from(c: Cone) -> Self {
+        Object::Cone(c)
+    }
+}
+
+impl From<Triangle> for Object {
+    fn from(t: Triangle) -> Self {
+        Object::Triangle(t)
+    }
+}
+
+impl From<SmoothTriangle> for Object {
+    fn from(t: SmoothTriangle) -> Self {
+        Object::SmoothTriangle(t)
+    }
+}
+
+impl From<Csg> for Object {
+    fn from(c: Csg) -> Self {
+        Object::Csg(c)
+    }
+}
+
+impl From<Group> for Object {
+    fn from(g: Group) -> Self {
+        Object::Group(g)
+    }
 }
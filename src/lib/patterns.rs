@@ -0,0 +1,340 @@
+use crate::{
+    canvas::Canvas,
+    color::Color,
+    matrix::{Matrix, MatrixError},
+    noise::{Noise, NoiseFunction},
+    objects::Object,
+    shapes::Placement,
+    textures::{Texture, UvMapping},
+    tuple::Point,
+};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum PatternKind {
+    /// Alternates between the colors every unit along x
+    Stripe(Color, Color),
+    /// Blends from the first color at x = 0 to the second at x = 1
+    Gradient(Color, Color),
+    /// Concentric rings around the y axis, one unit wide
+    Ring(Color, Color),
+    /// 3D checkerboard of unit cubes
+    Checker(Color, Color),
+    /// Blends between the colors by the value of the noise, for clouds and
+    /// other irregular surfaces
+    Noise(Color, Color, Noise, NoiseFunction),
+    /// Image projected onto the surface
+    Texture(Texture),
+}
+
+/// Color varying over a surface. Patterns have their own transform which is
+/// applied on top of the transform of the object they are drawn on.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Pattern {
+    kind: PatternKind,
+    placement: Placement,
+    // Noise jittering the point before the lookup, with its scale
+    perturbation: Option<(Noise, f64)>,
+}
+
+impl Pattern {
+    pub fn new(kind: PatternKind) -> Self {
+        Pattern {
+            kind,
+            placement: Placement::default(),
+            perturbation: None,
+        }
+    }
+
+    pub fn stripe(a: Color, b: Color) -> Self {
+        Pattern::new(PatternKind::Stripe(a, b))
+    }
+
+    pub fn gradient(a: Color, b: Color) -> Self {
+        Pattern::new(PatternKind::Gradient(a, b))
+    }
+
+    pub fn ring(a: Color, b: Color) -> Self {
+        Pattern::new(PatternKind::Ring(a, b))
+    }
+
+    pub fn checker(a: Color, b: Color) -> Self {
+        Pattern::new(PatternKind::Checker(a, b))
+    }
+
+    pub fn noise(a: Color, b: Color, noise: Noise, function: NoiseFunction) -> Self {
+        Pattern::new(PatternKind::Noise(a, b, noise, function))
+    }
+
+    pub fn texture(image: Canvas, mapping: UvMapping) -> Self {
+        Pattern::new(PatternKind::Texture(Texture::new(image, mapping)))
+    }
+
+    pub fn kind(&self) -> &PatternKind {
+        &self.kind
+    }
+
+    pub fn transform(&self) -> &Matrix<f64> {
+        self.placement.transform()
+    }
+
+    pub fn set_transform(&mut self, m: Matrix<f64>) {
+        self.placement.set_transform(m);
+    }
+
+    pub fn perturbation(&self) -> Option<&(Noise, f64)> {
+        self.perturbation.as_ref()
+    }
+
+    /// Move every point looked up by up to `scale` units following the noise.
+    /// A perturbed stripe gives marble, perturbed rings give wood grain.
+    pub fn set_perturbation(&mut self, noise: Noise, scale: f64) {
+        self.perturbation = Some((noise, scale));
+    }
+
+    /// Color at a point in pattern space
+    pub fn pattern_at(&self, p: Point) -> Color {
+        let p = match &self.perturbation {
+            Some((noise, scale)) => noise.perturb(p, *scale),
+            None => p,
+        };
+        let (x, y, z) = (p.0.x, p.0.y, p.0.z);
+        match &self.kind {
+            PatternKind::Stripe(a, b) => {
+                if x.floor() as i64 % 2 == 0 {
+                    *a
+                } else {
+                    *b
+                }
+            }
+            PatternKind::Gradient(a, b) => *a + (*b - *a) * (x - x.floor()),
+            PatternKind::Ring(a, b) => {
+                if (x * x + z * z).sqrt().floor() as i64 % 2 == 0 {
+                    *a
+                } else {
+                    *b
+                }
+            }
+            PatternKind::Checker(a, b) => {
+                if (x.floor() + y.floor() + z.floor()) as i64 % 2 == 0 {
+                    *a
+                } else {
+                    *b
+                }
+            }
+            PatternKind::Noise(a, b, noise, function) => {
+                let n = noise.eval(p, *function);
+                // Turbulence is already in [0, 1], the others in [-1, 1]
+                let t = match function {
+                    NoiseFunction::Turbulence(_) => n,
+                    _ => (n + 1.0) / 2.0,
+                };
+                *a + (*b - *a) * t.clamp(0.0, 1.0)
+            }
+            PatternKind::Texture(texture) => texture.color_at(p),
+        }
+    }
+
+    /// Color at a world space point on the object
+    pub fn pattern_at_object(&self, object: &Object, p: Point) -> Result<Color, MatrixError> {
+        let object_point = object.world_to_object(p)?;
+        Ok(self.pattern_at(self.placement.world_to_object(object_point)?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        color::CommonColor, groups::Group, shapes::Shape, spheres::Sphere, tuple::IsTuple,
+    };
+
+    use super::*;
+
+    fn black() -> Color {
+        CommonColor::Black.value()
+    }
+
+    fn white() -> Color {
+        CommonColor::White.value()
+    }
+
+    #[test]
+    fn test_stripe() {
+        let p = Pattern::stripe(white(), black());
+        // Constant in y and z
+        for (y, z) in [(0.0, 0.0), (1.0, 0.0), (2.0, 0.0), (0.0, 1.0), (0.0, 2.0)] {
+            assert_eq!(white(), p.pattern_at(Point::new(0.0, y, z)));
+        }
+        let cases = [
+            (0.0, white()),
+            (0.9, white()),
+            (1.0, black()),
+            (-0.1, black()),
+            (-1.0, black()),
+            (-1.1, white()),
+        ];
+        for (x, expected) in cases {
+            assert_eq!(expected, p.pattern_at(Point::new(x, 0.0, 0.0)));
+        }
+    }
+
+    #[test]
+    fn test_gradient() {
+        let p = Pattern::gradient(white(), black());
+        let cases = [
+            (0.0, white()),
+            (0.25, Color::new(0.75, 0.75, 0.75)),
+            (0.5, Color::new(0.5, 0.5, 0.5)),
+            (0.75, Color::new(0.25, 0.25, 0.25)),
+        ];
+        for (x, expected) in cases {
+            assert_eq!(expected, p.pattern_at(Point::new(x, 0.0, 0.0)));
+        }
+    }
+
+    #[test]
+    fn test_ring() {
+        let p = Pattern::ring(white(), black());
+        assert_eq!(white(), p.pattern_at(Point::new(0.0, 0.0, 0.0)));
+        assert_eq!(black(), p.pattern_at(Point::new(1.0, 0.0, 0.0)));
+        assert_eq!(black(), p.pattern_at(Point::new(0.0, 0.0, 1.0)));
+        // Just slightly more than sqrt(2) / 2
+        assert_eq!(black(), p.pattern_at(Point::new(0.708, 0.0, 0.708)));
+    }
+
+    #[test]
+    fn test_checker() {
+        let p = Pattern::checker(white(), black());
+        let cases = [
+            ([0.0, 0.0, 0.0], white()),
+            ([0.99, 0.0, 0.0], white()),
+            ([1.01, 0.0, 0.0], black()),
+            ([0.0, 0.99, 0.0], white()),
+            ([0.0, 1.01, 0.0], black()),
+            ([0.0, 0.0, 0.99], white()),
+            ([0.0, 0.0, 1.01], black()),
+            ([-0.5, 0.0, 0.0], black()),
+        ];
+        for (point, expected) in cases {
+            assert_eq!(expected, p.pattern_at(Point::from(point)));
+        }
+    }
+
+    #[test]
+    fn test_noise() {
+        let p = Pattern::noise(white(), black(), Noise::perlin(1), NoiseFunction::Plain);
+        // Perlin noise is zero on the lattice, halfway between the colors
+        assert_eq!(
+            Color::new(0.5, 0.5, 0.5),
+            p.pattern_at(Point::new(1.0, 2.0, 3.0))
+        );
+
+        let noise = Noise::simplex(1);
+        let p = Pattern::noise(
+            white(),
+            black(),
+            noise.clone(),
+            NoiseFunction::Turbulence(3),
+        );
+        let point = Point::new(0.3, 0.6, 0.9);
+        let t = noise.turbulence(point, 3);
+        assert_eq!(Color::new(1.0 - t, 1.0 - t, 1.0 - t), p.pattern_at(point));
+    }
+
+    #[test]
+    fn test_texture() {
+        // White top left and bottom right, black elsewhere
+        let mut image = Canvas::new(2, 2);
+        image.write_pixel(0, 0, white());
+        image.write_pixel(1, 1, white());
+        let mut p = Pattern::texture(image, UvMapping::Planar);
+        assert_eq!(black(), p.pattern_at(Point::new(0.0, 0.0, 0.0)));
+        let gray = Color::new(0.5, 0.5, 0.5);
+        assert_eq!(gray, p.pattern_at(Point::new(0.5, 0.0, 0.5)));
+
+        // Scaled up by the pattern transform, one copy every two units
+        p.set_transform(Matrix::scaling(2.0, 2.0, 2.0));
+        let object = Object::from(Sphere::default());
+        assert_eq!(
+            gray,
+            p.pattern_at_object(&object, Point::new(1.0, 0.0, 1.0))
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn test_perturbation() {
+        let mut p = Pattern::stripe(white(), black());
+        p.set_perturbation(Noise::perlin(2), 0.3);
+        // Well within a stripe the color doesn't change
+        assert_eq!(white(), p.pattern_at(Point::new(0.5, 0.2, 0.7)));
+        assert_eq!(black(), p.pattern_at(Point::new(1.5, 0.2, 0.7)));
+
+        // Near the edges it does
+        let unperturbed = Pattern::stripe(white(), black());
+        let moved = (0..100)
+            .map(|i| Point::new(0.95, i as f64 * 0.173, i as f64 * 0.091))
+            .filter(|&point| p.pattern_at(point) != unperturbed.pattern_at(point))
+            .count();
+        assert!(moved > 0 && moved < 100);
+    }
+
+    #[test]
+    fn test_pattern_at_object() {
+        // Object transformation
+        let mut s = Sphere::default();
+        s.set_transform(Matrix::scaling(2.0, 2.0, 2.0));
+        let object = Object::from(s);
+        let p = Pattern::stripe(white(), black());
+        assert_eq!(
+            white(),
+            p.pattern_at_object(&object, Point::new(1.5, 0.0, 0.0))
+                .unwrap()
+        );
+
+        // Pattern transformation
+        let object = Object::from(Sphere::default());
+        let mut p = Pattern::stripe(white(), black());
+        p.set_transform(Matrix::scaling(2.0, 2.0, 2.0));
+        assert_eq!(
+            white(),
+            p.pattern_at_object(&object, Point::new(1.5, 0.0, 0.0))
+                .unwrap()
+        );
+
+        // Both
+        let mut s = Sphere::default();
+        s.set_transform(Matrix::scaling(2.0, 2.0, 2.0));
+        let object = Object::from(s);
+        let mut p = Pattern::stripe(white(), black());
+        p.set_transform(Matrix::translation(0.5, 0.0, 0.0));
+        assert_eq!(
+            white(),
+            p.pattern_at_object(&object, Point::new(2.5, 0.0, 0.0))
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn test_pattern_at_object_in_group() {
+        // The group's transform is taken into account as well
+        let mut g = Group::new();
+        g.set_transform(Matrix::translation(1.0, 0.0, 0.0));
+        g.add_child(Sphere::default());
+        let p = Pattern::stripe(white(), black());
+        let s = &g.children()[0];
+        assert_eq!(
+            black(),
+            p.pattern_at_object(s, Point::new(0.5, 0.0, 0.0)).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_not_invertible() {
+        let mut p = Pattern::stripe(white(), black());
+        p.set_transform(Matrix::scaling(0.0, 1.0, 1.0));
+        let object = Object::from(Sphere::default());
+        assert!(p
+            .pattern_at_object(&object, Point::new(0.0, 0.0, 0.0))
+            .is_err());
+    }
+}
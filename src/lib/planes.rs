@@ -0,0 +1,115 @@
+use crate::{
+    bounds::BoundingBox,
+    intersections::Intersection,
+    materials::Material,
+    rays::Ray,
+    shapes::{Placement, Shape},
+    tuple::{IsTuple, Point, Vector},
+    utils::EPSILON,
+};
+
+/// Infinite plane, lying on xz in object space
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Plane {
+    placement: Placement,
+    pub material: Material,
+}
+
+impl Plane {
+    pub fn new() -> Self {
+        Plane::default()
+    }
+}
+
+impl Shape for Plane {
+    fn placement(&self) -> &Placement {
+        &self.placement
+    }
+
+    fn placement_mut(&mut self) -> &mut Placement {
+        &mut self.placement
+    }
+
+    fn material(&self) -> &Material {
+        &self.material
+    }
+
+    fn material_mut(&mut self) -> &mut Material {
+        &mut self.material
+    }
+
+    fn local_intersect(&self, r: Ray) -> Vec<Intersection<'_, Plane>> {
+        // A ray parallel to (or within) the plane never hits it
+        if r.direction.0.y.abs() < EPSILON {
+            return Vec::new();
+        }
+        let t = -r.origin.0.y / r.direction.0.y;
+        vec![Intersection::new(t, self)]
+    }
+
+    fn local_normal_at(&self, _p: Point) -> Vector {
+        Vector::new(0.0, 1.0, 0.0)
+    }
+
+    fn bounds(&self) -> BoundingBox {
+        BoundingBox::new(
+            Point::new(f64::NEG_INFINITY, 0.0, f64::NEG_INFINITY),
+            Point::new(f64::INFINITY, 0.0, f64::INFINITY),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normal_is_constant() {
+        let p = Plane::new();
+        let n = Vector::new(0.0, 1.0, 0.0);
+        assert_eq!(n, p.local_normal_at(Point::new(0.0, 0.0, 0.0)));
+        assert_eq!(n, p.local_normal_at(Point::new(10.0, 0.0, -10.0)));
+        assert_eq!(n, p.local_normal_at(Point::new(-5.0, 0.0, 150.0)));
+    }
+
+    #[test]
+    fn test_intersect_parallel() {
+        let p = Plane::new();
+        let r = Ray::new(Point::new(0.0, 10.0, 0.0), Vector::new(0.0, 0.0, 1.0));
+        assert_eq!(0, p.local_intersect(r).len());
+    }
+
+    #[test]
+    fn test_intersect_coplanar() {
+        let p = Plane::new();
+        let r = Ray::new(Point::new(0.0, 0.0, 0.0), Vector::new(0.0, 0.0, 1.0));
+        assert_eq!(0, p.local_intersect(r).len());
+    }
+
+    #[test]
+    fn test_intersect_from_above() {
+        let p = Plane::new();
+        let r = Ray::new(Point::new(0.0, 1.0, 0.0), Vector::new(0.0, -1.0, 0.0));
+        let xs = p.local_intersect(r);
+        assert_eq!(1, xs.len());
+        assert_eq!(1.0, xs[0].time);
+        assert_eq!(&p, xs[0].object);
+    }
+
+    #[test]
+    fn test_intersect_from_below() {
+        let p = Plane::new();
+        let r = Ray::new(Point::new(0.0, -1.0, 0.0), Vector::new(0.0, 1.0, 0.0));
+        let xs = p.local_intersect(r);
+        assert_eq!(1, xs.len());
+        assert_eq!(1.0, xs[0].time);
+        assert_eq!(&p, xs[0].object);
+    }
+
+    #[test]
+    fn test_bounds() {
+        let b = Plane::new().bounds();
+        assert_eq!(Point::new(f64::NEG_INFINITY, 0.0, f64::NEG_INFINITY), b.min);
+        assert_eq!(Point::new(f64::INFINITY, 0.0, f64::INFINITY), b.max);
+    }
+}
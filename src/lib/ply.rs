@@ -0,0 +1,636 @@
+use thiserror::Error;
+
+use crate::{
+    color::Color,
+    groups::Group,
+    objects::Object,
+    triangles::{SmoothTriangle, Triangle},
+    tuple::{IsTuple, Point, Vector},
+};
+
+#[derive(Error, Debug, PartialEq)]
+pub enum PlyError {
+    #[error("Invalid header: {0}")]
+    InvalidHeader(String),
+    #[error("Unsupported format {0}")]
+    UnsupportedFormat(String),
+    #[error("Invalid data: {0}")]
+    InvalidData(String),
+    #[error("Unexpected end of data")]
+    UnexpectedEnd,
+    #[error("Face {face}: vertex index {index} is out of range")]
+    IndexOutOfRange { face: usize, index: i64 },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Format {
+    Ascii,
+    BinaryLittleEndian,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Scalar {
+    Int8,
+    UInt8,
+    Int16,
+    UInt16,
+    Int32,
+    UInt32,
+    Float32,
+    Float64,
+}
+
+impl Scalar {
+    fn parse(name: &str) -> Result<Self, PlyError> {
+        match name {
+            "char" | "int8" => Ok(Scalar::Int8),
+            "uchar" | "uint8" => Ok(Scalar::UInt8),
+            "short" | "int16" => Ok(Scalar::Int16),
+            "ushort" | "uint16" => Ok(Scalar::UInt16),
+            "int" | "int32" => Ok(Scalar::Int32),
+            "uint" | "uint32" => Ok(Scalar::UInt32),
+            "float" | "float32" => Ok(Scalar::Float32),
+            "double" | "float64" => Ok(Scalar::Float64),
+            _ => Err(PlyError::InvalidHeader(format!(
+                "unknown property type {:?}",
+                name
+            ))),
+        }
+    }
+
+    fn is_float(&self) -> bool {
+        matches!(self, Scalar::Float32 | Scalar::Float64)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum PropertyKind {
+    Scalar(Scalar),
+    List { count: Scalar, item: Scalar },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Property {
+    name: String,
+    kind: PropertyKind,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Element {
+    name: String,
+    count: usize,
+    properties: Vec<Property>,
+}
+
+impl Element {
+    fn scalar(&self, name: &str) -> Option<(usize, Scalar)> {
+        self.properties
+            .iter()
+            .enumerate()
+            .find_map(|(i, p)| match p.kind {
+                PropertyKind::Scalar(s) if p.name == name => Some((i, s)),
+                _ => None,
+            })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Value {
+    Scalar(f64),
+    List(Vec<f64>),
+}
+
+impl Value {
+    fn scalar(&self) -> f64 {
+        match self {
+            Value::Scalar(x) => *x,
+            Value::List(_) => f64::NAN,
+        }
+    }
+}
+
+/// Source of the values in a PLY body, one scalar at a time
+trait BodyReader {
+    fn read(&mut self, s: Scalar) -> Result<f64, PlyError>;
+
+    fn read_row(&mut self, element: &Element) -> Result<Vec<Value>, PlyError> {
+        let mut row = Vec::with_capacity(element.properties.len());
+        for property in element.properties.iter() {
+            row.push(match property.kind {
+                PropertyKind::Scalar(s) => Value::Scalar(self.read(s)?),
+                PropertyKind::List { count, item } => {
+                    let count = self.read(count)?;
+                    if count < 0.0 {
+                        return Err(PlyError::InvalidData(format!(
+                            "negative list length {}",
+                            count
+                        )));
+                    }
+                    let items = (0..count as usize)
+                        .map(|_| self.read(item))
+                        .collect::<Result<_, _>>()?;
+                    Value::List(items)
+                }
+            });
+        }
+        Ok(row)
+    }
+}
+
+struct AsciiReader<'a> {
+    tokens: std::str::SplitAsciiWhitespace<'a>,
+}
+
+impl BodyReader for AsciiReader<'_> {
+    fn read(&mut self, _s: Scalar) -> Result<f64, PlyError> {
+        let token = self.tokens.next().ok_or(PlyError::UnexpectedEnd)?;
+        token
+            .parse()
+            .map_err(|_| PlyError::InvalidData(format!("invalid number {:?}", token)))
+    }
+}
+
+struct BinaryReader<'a> {
+    data: &'a [u8],
+}
+
+impl BinaryReader<'_> {
+    fn take<const N: usize>(&mut self) -> Result<[u8; N], PlyError> {
+        if self.data.len() < N {
+            return Err(PlyError::UnexpectedEnd);
+        }
+        let (bytes, rest) = self.data.split_at(N);
+        self.data = rest;
+        let mut out = [0; N];
+        out.copy_from_slice(bytes);
+        Ok(out)
+    }
+}
+
+impl BodyReader for BinaryReader<'_> {
+    fn read(&mut self, s: Scalar) -> Result<f64, PlyError> {
+        Ok(match s {
+            Scalar::Int8 => i8::from_le_bytes(self.take()?) as f64,
+            Scalar::UInt8 => u8::from_le_bytes(self.take()?) as f64,
+            Scalar::Int16 => i16::from_le_bytes(self.take()?) as f64,
+            Scalar::UInt16 => u16::from_le_bytes(self.take()?) as f64,
+            Scalar::Int32 => i32::from_le_bytes(self.take()?) as f64,
+            Scalar::UInt32 => u32::from_le_bytes(self.take()?) as f64,
+            Scalar::Float32 => f32::from_le_bytes(self.take()?) as f64,
+            Scalar::Float64 => f64::from_le_bytes(self.take()?),
+        })
+    }
+}
+
+/// Stanford PLY mesh, with an ascii or binary little endian body.
+/// Vertices need `x`, `y` and `z` properties, `nx`/`ny`/`nz` normals and
+/// `red`/`green`/`blue` colors are picked up when present.
+/// Faces are fan triangulated.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct PlyFile {
+    vertices: Vec<Point>,
+    normals: Vec<Vector>,
+    colors: Vec<Color>,
+    faces: Vec<Vec<usize>>,
+}
+
+impl PlyFile {
+    pub fn parse(bytes: &[u8]) -> Result<Self, PlyError> {
+        let (format, elements, body) = parse_header(bytes)?;
+        let mut reader: Box<dyn BodyReader> = match format {
+            Format::Ascii => {
+                let body = std::str::from_utf8(body)
+                    .map_err(|_| PlyError::InvalidData("ascii body is not utf-8".to_string()))?;
+                Box::new(AsciiReader {
+                    tokens: body.split_ascii_whitespace(),
+                })
+            }
+            Format::BinaryLittleEndian => Box::new(BinaryReader { data: body }),
+        };
+
+        let mut ply = PlyFile::default();
+        for element in elements.iter() {
+            match element.name.as_str() {
+                "vertex" => ply.read_vertices(element, reader.as_mut())?,
+                "face" => ply.read_faces(element, reader.as_mut())?,
+                // Other elements still have to be read to get past them
+                _ => {
+                    for _ in 0..element.count {
+                        reader.read_row(element)?;
+                    }
+                }
+            }
+        }
+
+        for (face, indices) in ply.faces.iter().enumerate() {
+            if let Some(&index) = indices.iter().find(|&&i| i >= ply.vertices.len()) {
+                return Err(PlyError::IndexOutOfRange {
+                    face,
+                    index: index as i64,
+                });
+            }
+        }
+        Ok(ply)
+    }
+
+    fn read_vertices(
+        &mut self,
+        element: &Element,
+        reader: &mut dyn BodyReader,
+    ) -> Result<(), PlyError> {
+        let position = ["x", "y", "z"]
+            .iter()
+            .map(|name| element.scalar(name))
+            .collect::<Option<Vec<_>>>()
+            .ok_or_else(|| PlyError::InvalidHeader("vertex needs x, y and z".to_string()))?;
+        let normal = ["nx", "ny", "nz"]
+            .iter()
+            .map(|name| element.scalar(name))
+            .collect::<Option<Vec<_>>>();
+        let color = ["red", "green", "blue"]
+            .iter()
+            .map(|name| element.scalar(name))
+            .collect::<Option<Vec<_>>>();
+
+        for _ in 0..element.count {
+            let row = reader.read_row(element)?;
+            let get = |(i, _): (usize, Scalar)| row[i].scalar();
+            self.vertices.push(Point::new(
+                get(position[0]),
+                get(position[1]),
+                get(position[2]),
+            ));
+            if let Some(n) = &normal {
+                self.normals
+                    .push(Vector::new(get(n[0]), get(n[1]), get(n[2])));
+            }
+            if let Some(c) = &color {
+                // Integer channels run from 0 to 255, floats from 0 to 1
+                let channel = |p: (usize, Scalar)| {
+                    if p.1.is_float() {
+                        get(p)
+                    } else {
+                        get(p) / 255.0
+                    }
+                };
+                self.colors
+                    .push(Color::new(channel(c[0]), channel(c[1]), channel(c[2])));
+            }
+        }
+        Ok(())
+    }
+
+    fn read_faces(
+        &mut self,
+        element: &Element,
+        reader: &mut dyn BodyReader,
+    ) -> Result<(), PlyError> {
+        let index = element
+            .properties
+            .iter()
+            .position(|p| {
+                matches!(p.kind, PropertyKind::List { .. })
+                    && (p.name == "vertex_indices" || p.name == "vertex_index")
+            })
+            .ok_or_else(|| {
+                PlyError::InvalidHeader("face needs a vertex_indices list".to_string())
+            })?;
+
+        for face in 0..element.count {
+            let row = reader.read_row(element)?;
+            let indices = match &row[index] {
+                Value::List(indices) => indices,
+                Value::Scalar(_) => unreachable!("vertex_indices is a list property"),
+            };
+            if indices.len() < 3 {
+                return Err(PlyError::InvalidData(format!(
+                    "face {} needs at least 3 vertices, got {}",
+                    face,
+                    indices.len()
+                )));
+            }
+            if let Some(&i) = indices.iter().find(|&&i| i < 0.0) {
+                return Err(PlyError::IndexOutOfRange {
+                    face,
+                    index: i as i64,
+                });
+            }
+            self.faces
+                .push(indices.iter().map(|&i| i as usize).collect());
+        }
+        Ok(())
+    }
+
+    pub fn vertices(&self) -> &[Point] {
+        &self.vertices
+    }
+
+    /// Vertex normals, empty if the file has none
+    pub fn normals(&self) -> &[Vector] {
+        &self.normals
+    }
+
+    /// Vertex colors, empty if the file has none
+    pub fn colors(&self) -> &[Color] {
+        &self.colors
+    }
+
+    /// Vertex indices of every face, 0-based
+    pub fn faces(&self) -> &[Vec<usize>] {
+        &self.faces
+    }
+
+    /// Triangles for every face. They are smooth if the file has vertex
+    /// normals, and colored with the average of their vertices' colors.
+    pub fn triangles(&self) -> Vec<Object> {
+        let mut triangles = Vec::new();
+        for face in self.faces.iter() {
+            // Fan out from the first vertex
+            for i in 1..face.len() - 1 {
+                let (a, b, c) = (face[0], face[i], face[i + 1]);
+                let (p1, p2, p3) = (self.vertices[a], self.vertices[b], self.vertices[c]);
+                let mut t: Object = if self.normals.is_empty() {
+                    Triangle::new(p1, p2, p3).into()
+                } else {
+                    let (n1, n2, n3) = (self.normals[a], self.normals[b], self.normals[c]);
+                    SmoothTriangle::new(p1, p2, p3, n1, n2, n3).into()
+                };
+                if !self.colors.is_empty() {
+                    t.material_mut().color =
+                        (self.colors[a] + self.colors[b] + self.colors[c]) * (1.0 / 3.0);
+                }
+                triangles.push(t);
+            }
+        }
+        triangles
+    }
+
+    /// Single group holding every triangle of the mesh
+    pub fn into_group(self) -> Group {
+        let mut mesh = Group::new();
+        for t in self.triangles() {
+            mesh.add_child(t);
+        }
+        mesh
+    }
+}
+
+impl From<PlyFile> for Object {
+    fn from(ply: PlyFile) -> Self {
+        ply.into_group().into()
+    }
+}
+
+/// Format, elements and the remaining body of a PLY file
+fn parse_header(bytes: &[u8]) -> Result<(Format, Vec<Element>, &[u8]), PlyError> {
+    let mut format = None;
+    let mut elements: Vec<Element> = Vec::new();
+    let mut rest = bytes;
+
+    for line_num in 1.. {
+        let end = rest
+            .iter()
+            .position(|&b| b == b'\n')
+            .ok_or_else(|| PlyError::InvalidHeader("missing end_header".to_string()))?;
+        let line = std::str::from_utf8(&rest[..end])
+            .map_err(|_| PlyError::InvalidHeader("header is not ascii".to_string()))?;
+        rest = &rest[end + 1..];
+        let tokens: Vec<&str> = line.split_whitespace().collect();
+
+        if line_num == 1 {
+            if tokens != ["ply"] {
+                return Err(PlyError::InvalidHeader("missing ply magic".to_string()));
+            }
+            continue;
+        }
+
+        match tokens.as_slice() {
+            ["format", "ascii", _] => format = Some(Format::Ascii),
+            ["format", "binary_little_endian", _] => format = Some(Format::BinaryLittleEndian),
+            ["format", other, ..] => return Err(PlyError::UnsupportedFormat(other.to_string())),
+            ["element", name, count] => elements.push(Element {
+                name: name.to_string(),
+                count: count.parse().map_err(|_| {
+                    PlyError::InvalidHeader(format!("invalid element count {:?}", count))
+                })?,
+                properties: Vec::new(),
+            }),
+            ["property", "list", count, item, name] => {
+                let kind = PropertyKind::List {
+                    count: Scalar::parse(count)?,
+                    item: Scalar::parse(item)?,
+                };
+                add_property(&mut elements, name, kind)?;
+            }
+            ["property", ty, name] => {
+                let kind = PropertyKind::Scalar(Scalar::parse(ty)?);
+                add_property(&mut elements, name, kind)?;
+            }
+            ["comment", ..] | ["obj_info", ..] | [] => {}
+            ["end_header"] => break,
+            _ => {
+                return Err(PlyError::InvalidHeader(format!(
+                    "line {}: unexpected {:?}",
+                    line_num, line
+                )))
+            }
+        }
+    }
+
+    let format = format.ok_or_else(|| PlyError::InvalidHeader("missing format".to_string()))?;
+    Ok((format, elements, rest))
+}
+
+fn add_property(elements: &mut [Element], name: &str, kind: PropertyKind) -> Result<(), PlyError> {
+    let element = elements
+        .last_mut()
+        .ok_or_else(|| PlyError::InvalidHeader(format!("property {} outside element", name)))?;
+    element.properties.push(Property {
+        name: name.to_string(),
+        kind,
+    });
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SQUARE: &str = "ply\n\
+                          format ascii 1.0\n\
+                          comment unit square\n\
+                          element vertex 4\n\
+                          property float x\n\
+                          property float y\n\
+                          property float z\n\
+                          element face 1\n\
+                          property list uchar int vertex_indices\n\
+                          end_header\n\
+                          0 0 0\n\
+                          1 0 0\n\
+                          1 1 0\n\
+                          0 1 0\n\
+                          4 0 1 2 3\n";
+
+    #[test]
+    fn test_ascii() {
+        let ply = PlyFile::parse(SQUARE.as_bytes()).unwrap();
+        assert_eq!(4, ply.vertices().len());
+        assert_eq!(Point::new(1.0, 1.0, 0.0), ply.vertices()[2]);
+        assert!(ply.normals().is_empty());
+        assert!(ply.colors().is_empty());
+        assert_eq!(&[vec![0, 1, 2, 3]], ply.faces());
+
+        // Quad is split into two triangles
+        let triangles = ply.triangles();
+        assert_eq!(2, triangles.len());
+        match &triangles[1] {
+            Object::Triangle(t) => {
+                assert_eq!(Point::new(0.0, 0.0, 0.0), t.p1());
+                assert_eq!(Point::new(1.0, 1.0, 0.0), t.p2());
+                assert_eq!(Point::new(0.0, 1.0, 0.0), t.p3());
+            }
+            _ => panic!("Expected a triangle"),
+        }
+    }
+
+    #[test]
+    fn test_ascii_normals_and_colors() {
+        let content = "ply\n\
+                       format ascii 1.0\n\
+                       element vertex 3\n\
+                       property float x\n\
+                       property float y\n\
+                       property float z\n\
+                       property float nx\n\
+                       property float ny\n\
+                       property float nz\n\
+                       property uchar red\n\
+                       property uchar green\n\
+                       property uchar blue\n\
+                       element edge 1\n\
+                       property int vertex1\n\
+                       property int vertex2\n\
+                       element face 1\n\
+                       property list uchar uint vertex_index\n\
+                       end_header\n\
+                       0 1 0 0 1 0 255 0 0\n\
+                       -1 0 0 -1 0 0 0 255 0\n\
+                       1 0 0 1 0 0 0 0 255\n\
+                       0 1\n\
+                       3 0 1 2\n";
+        let ply = PlyFile::parse(content.as_bytes()).unwrap();
+        assert_eq!(Vector::new(-1.0, 0.0, 0.0), ply.normals()[1]);
+        assert_eq!(Color::new(0.0, 1.0, 0.0), ply.colors()[1]);
+
+        let triangles = ply.triangles();
+        assert_eq!(1, triangles.len());
+        match &triangles[0] {
+            Object::SmoothTriangle(t) => assert_eq!(Vector::new(1.0, 0.0, 0.0), t.n3()),
+            _ => panic!("Expected a smooth triangle"),
+        }
+        assert_eq!(
+            Color::new(1.0 / 3.0, 1.0 / 3.0, 1.0 / 3.0),
+            triangles[0].material().color
+        );
+    }
+
+    #[test]
+    fn test_binary_little_endian() {
+        let mut content = b"ply\n\
+                            format binary_little_endian 1.0\n\
+                            element vertex 3\n\
+                            property float x\n\
+                            property float y\n\
+                            property double z\n\
+                            element face 1\n\
+                            property list uchar ushort vertex_indices\n\
+                            end_header\n"
+            .to_vec();
+        for (x, y, z) in [(0.0f32, 1.0f32, 0.5f64), (-1.0, 0.0, 0.5), (1.0, 0.0, 0.5)] {
+            content.extend_from_slice(&x.to_le_bytes());
+            content.extend_from_slice(&y.to_le_bytes());
+            content.extend_from_slice(&z.to_le_bytes());
+        }
+        content.push(3);
+        for i in [0u16, 1, 2] {
+            content.extend_from_slice(&i.to_le_bytes());
+        }
+
+        let ply = PlyFile::parse(&content).unwrap();
+        assert_eq!(
+            &[
+                Point::new(0.0, 1.0, 0.5),
+                Point::new(-1.0, 0.0, 0.5),
+                Point::new(1.0, 0.0, 0.5),
+            ],
+            ply.vertices()
+        );
+        assert_eq!(&[vec![0, 1, 2]], ply.faces());
+
+        // Cut off in the middle of the face
+        assert_eq!(
+            Err(PlyError::UnexpectedEnd),
+            PlyFile::parse(&content[..content.len() - 1])
+        );
+    }
+
+    #[test]
+    fn test_into_group() {
+        let g = PlyFile::parse(SQUARE.as_bytes()).unwrap().into_group();
+        assert_eq!(2, g.children().len());
+        assert!(matches!(
+            Object::from(PlyFile::parse(SQUARE.as_bytes()).unwrap()),
+            Object::Group(_)
+        ));
+    }
+
+    #[test]
+    fn test_invalid_header() {
+        let cases = [
+            "plx\nformat ascii 1.0\nend_header\n",
+            "ply\nelement vertex 1\nproperty float x\nend_header\n",
+            "ply\nformat ascii 1.0\nproperty float x\nend_header\n",
+            "ply\nformat ascii 1.0\nelement vertex 1\nproperty quad x\nend_header\n",
+            "ply\nformat ascii 1.0\nelement vertex 1\nproperty float x\nend_header\n0\n",
+            "ply\nformat ascii 1.0\nelement vertex 0\n",
+        ];
+        for content in cases {
+            assert!(matches!(
+                PlyFile::parse(content.as_bytes()),
+                Err(PlyError::InvalidHeader(_))
+            ));
+        }
+        assert_eq!(
+            Err(PlyError::UnsupportedFormat("binary_big_endian".to_string())),
+            PlyFile::parse(b"ply\nformat binary_big_endian 1.0\nend_header\n")
+        );
+    }
+
+    #[test]
+    fn test_invalid_body() {
+        let bad_number = SQUARE.replace("1 1 0", "1 one 0");
+        assert!(matches!(
+            PlyFile::parse(bad_number.as_bytes()),
+            Err(PlyError::InvalidData(_))
+        ));
+
+        let out_of_range = SQUARE.replace("4 0 1 2 3", "4 0 1 2 4");
+        assert_eq!(
+            Err(PlyError::IndexOutOfRange { face: 0, index: 4 }),
+            PlyFile::parse(out_of_range.as_bytes())
+        );
+
+        let truncated = SQUARE.replace("4 0 1 2 3", "4 0 1 2");
+        assert_eq!(
+            Err(PlyError::UnexpectedEnd),
+            PlyFile::parse(truncated.as_bytes())
+        );
+
+        let degenerate = SQUARE.replace("4 0 1 2 3", "2 0 1");
+        assert!(matches!(
+            PlyFile::parse(degenerate.as_bytes()),
+            Err(PlyError::InvalidData(_))
+        ));
+    }
+}
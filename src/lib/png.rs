@@ -0,0 +1,370 @@
+use std::io::{self, Write};
+
+const SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1a, b'\n'];
+
+/// Largest amount of data a single stored deflate block can hold
+const MAX_STORED_BLOCK: usize = 65535;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColorType {
+    Rgb,
+    Rgba,
+}
+
+impl ColorType {
+    fn channels(&self) -> usize {
+        match self {
+            ColorType::Rgb => 3,
+            ColorType::Rgba => 4,
+        }
+    }
+
+    // Color type field of the IHDR chunk
+    fn code(&self) -> u8 {
+        match self {
+            ColorType::Rgb => 2,
+            ColorType::Rgba => 6,
+        }
+    }
+}
+
+/// Streaming encoder for 8 bit PNG images.
+/// Rows are written one at a time and the image data is stored without
+/// compression, so memory use stays at one deflate block whatever the
+/// image size.
+pub struct PngWriter<W: Write> {
+    out: W,
+    row_len: usize,
+    rows_left: usize,
+    block: Vec<u8>,
+    adler: Adler32,
+    header_written: bool,
+}
+
+impl<W: Write> PngWriter<W> {
+    /// Write the signature and header of a `width` by `height` image
+    pub fn new(mut out: W, width: usize, height: usize, color_type: ColorType) -> io::Result<Self> {
+        // Dimensions are limited to 2^31 - 1 by the format
+        let too_large = |v: usize| v == 0 || v > i32::MAX as usize;
+        if too_large(width) || too_large(height) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("Invalid PNG size {}x{}", width, height),
+            ));
+        }
+
+        out.write_all(&SIGNATURE)?;
+        let mut ihdr = Vec::with_capacity(13);
+        ihdr.extend_from_slice(&(width as u32).to_be_bytes());
+        ihdr.extend_from_slice(&(height as u32).to_be_bytes());
+        // Bit depth, color type, then default compression, filter and
+        // interlace methods
+        ihdr.extend_from_slice(&[8, color_type.code(), 0, 0, 0]);
+        write_chunk(&mut out, b"IHDR", &ihdr)?;
+
+        Ok(PngWriter {
+            out,
+            row_len: width * color_type.channels(),
+            rows_left: height,
+            block: Vec::with_capacity(MAX_STORED_BLOCK),
+            adler: Adler32::new(),
+            header_written: false,
+        })
+    }
+
+    /// Write the next row of samples, top to bottom
+    pub fn write_row(&mut self, row: &[u8]) -> io::Result<()> {
+        if row.len() != self.row_len {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("Expected {} bytes per row, got {}", self.row_len, row.len()),
+            ));
+        }
+        if self.rows_left == 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "All rows have already been written",
+            ));
+        }
+        self.rows_left -= 1;
+        // Every row starts with its filter type, none here
+        self.push(&[0])?;
+        self.push(row)
+    }
+
+    /// Write the end of the image, all rows must have been written
+    pub fn finish(mut self) -> io::Result<W> {
+        if self.rows_left > 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("{} rows are missing", self.rows_left),
+            ));
+        }
+        self.flush_block(true)?;
+        write_chunk(&mut self.out, b"IEND", &[])?;
+        Ok(self.out)
+    }
+
+    fn push(&mut self, mut data: &[u8]) -> io::Result<()> {
+        self.adler.update(data);
+        while !data.is_empty() {
+            if self.block.len() == MAX_STORED_BLOCK {
+                self.flush_block(false)?;
+            }
+            let n = data.len().min(MAX_STORED_BLOCK - self.block.len());
+            self.block.extend_from_slice(&data[..n]);
+            data = &data[n..];
+        }
+        Ok(())
+    }
+
+    // Write the pending data as a stored deflate block in its own IDAT
+    // chunk. The zlib stream runs across all the IDAT chunks, with its
+    // header in the first and its checksum in the last.
+    fn flush_block(&mut self, last: bool) -> io::Result<()> {
+        let mut idat = Vec::with_capacity(self.block.len() + 11);
+        if !self.header_written {
+            // Deflate with a 32K window, no preset dictionary
+            idat.extend_from_slice(&[0x78, 0x01]);
+            self.header_written = true;
+        }
+        let len = self.block.len() as u16;
+        idat.push(last as u8);
+        idat.extend_from_slice(&len.to_le_bytes());
+        idat.extend_from_slice(&(!len).to_le_bytes());
+        idat.extend_from_slice(&self.block);
+        if last {
+            idat.extend_from_slice(&self.adler.value().to_be_bytes());
+        }
+        self.block.clear();
+        write_chunk(&mut self.out, b"IDAT", &idat)
+    }
+}
+
+fn write_chunk<W: Write>(out: &mut W, kind: &[u8; 4], data: &[u8]) -> io::Result<()> {
+    out.write_all(&(data.len() as u32).to_be_bytes())?;
+    out.write_all(kind)?;
+    out.write_all(data)?;
+    let mut crc = Crc32::new();
+    crc.update(kind);
+    crc.update(data);
+    out.write_all(&crc.value().to_be_bytes())
+}
+
+/// CRC-32 as used by PNG chunks and zip files
+#[derive(Debug, Clone)]
+pub struct Crc32 {
+    table: [u32; 256],
+    crc: u32,
+}
+
+impl Default for Crc32 {
+    fn default() -> Self {
+        Crc32::new()
+    }
+}
+
+impl Crc32 {
+    pub fn new() -> Self {
+        let mut table = [0; 256];
+        for (n, entry) in table.iter_mut().enumerate() {
+            let mut c = n as u32;
+            for _ in 0..8 {
+                c = if c & 1 == 1 {
+                    0xedb8_8320 ^ (c >> 1)
+                } else {
+                    c >> 1
+                };
+            }
+            *entry = c;
+        }
+        Crc32 {
+            table,
+            crc: 0xffff_ffff,
+        }
+    }
+
+    pub fn update(&mut self, data: &[u8]) {
+        for &b in data {
+            self.crc = self.table[((self.crc ^ b as u32) & 0xff) as usize] ^ (self.crc >> 8);
+        }
+    }
+
+    pub fn value(&self) -> u32 {
+        self.crc ^ 0xffff_ffff
+    }
+}
+
+/// Adler-32 checksum ending every zlib stream
+#[derive(Debug, Clone, Copy)]
+pub struct Adler32 {
+    a: u32,
+    b: u32,
+}
+
+impl Default for Adler32 {
+    fn default() -> Self {
+        Adler32::new()
+    }
+}
+
+impl Adler32 {
+    const MOD: u32 = 65521;
+
+    pub fn new() -> Self {
+        Adler32 { a: 1, b: 0 }
+    }
+
+    pub fn update(&mut self, data: &[u8]) {
+        // Largest run of bytes after which b can't have overflowed yet
+        for chunk in data.chunks(5552) {
+            for &byte in chunk {
+                self.a += byte as u32;
+                self.b += self.a;
+            }
+            self.a %= Adler32::MOD;
+            self.b %= Adler32::MOD;
+        }
+    }
+
+    pub fn value(&self) -> u32 {
+        (self.b << 16) | self.a
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::convert::TryInto;
+
+    use super::*;
+
+    /// Chunks of a PNG file as (type, data), checking the signature and
+    /// every CRC along the way
+    fn read_chunks(png: &[u8]) -> Vec<(String, Vec<u8>)> {
+        assert_eq!(SIGNATURE, png[..8]);
+        let mut chunks = Vec::new();
+        let mut pos = 8;
+        while pos < png.len() {
+            let len = u32::from_be_bytes(png[pos..pos + 4].try_into().unwrap()) as usize;
+            let kind = &png[pos + 4..pos + 8];
+            let data = &png[pos + 8..pos + 8 + len];
+            let mut crc = Crc32::new();
+            crc.update(kind);
+            crc.update(data);
+            let stored = u32::from_be_bytes(png[pos + 8 + len..pos + 12 + len].try_into().unwrap());
+            assert_eq!(crc.value(), stored);
+            chunks.push((String::from_utf8(kind.to_vec()).unwrap(), data.to_vec()));
+            pos += 12 + len;
+        }
+        chunks
+    }
+
+    /// Raw image data of a PNG file made of stored deflate blocks
+    fn read_image_data(png: &[u8]) -> Vec<u8> {
+        let zlib: Vec<u8> = read_chunks(png)
+            .into_iter()
+            .filter(|(kind, _)| kind == "IDAT")
+            .flat_map(|(_, data)| data)
+            .collect();
+        assert_eq!(0, u16::from_be_bytes([zlib[0], zlib[1]]) % 31);
+        let mut pos = 2;
+        let mut data = Vec::new();
+        loop {
+            let header = zlib[pos];
+            let len = u16::from_le_bytes([zlib[pos + 1], zlib[pos + 2]]);
+            let nlen = u16::from_le_bytes([zlib[pos + 3], zlib[pos + 4]]);
+            assert_eq!(0, header & 0b110, "Expected a stored block");
+            assert_eq!(!len, nlen);
+            data.extend_from_slice(&zlib[pos + 5..pos + 5 + len as usize]);
+            pos += 5 + len as usize;
+            if header & 1 == 1 {
+                break;
+            }
+        }
+        let mut adler = Adler32::new();
+        adler.update(&data);
+        assert_eq!(adler.value().to_be_bytes(), zlib[pos..pos + 4]);
+        assert_eq!(pos + 4, zlib.len());
+        data
+    }
+
+    #[test]
+    fn test_crc32() {
+        let mut crc = Crc32::new();
+        crc.update(b"IEND");
+        assert_eq!(0xae42_6082, crc.value());
+
+        let mut crc = Crc32::new();
+        crc.update(b"1234");
+        crc.update(b"56789");
+        assert_eq!(0xcbf4_3926, crc.value());
+    }
+
+    #[test]
+    fn test_adler32() {
+        let mut adler = Adler32::new();
+        adler.update(b"Wikipedia");
+        assert_eq!(0x11e6_0398, adler.value());
+
+        // Long runs of large bytes must not overflow
+        let mut adler = Adler32::new();
+        adler.update(&vec![0xff; 100_000]);
+        let (mut a, mut b) = (1_u64, 0_u64);
+        for _ in 0..100_000 {
+            a = (a + 0xff) % 65521;
+            b = (b + a) % 65521;
+        }
+        assert_eq!(((b << 16) | a) as u32, adler.value());
+    }
+
+    #[test]
+    fn test_write_rgba() {
+        let mut w = PngWriter::new(Vec::new(), 2, 1, ColorType::Rgba).unwrap();
+        w.write_row(&[255, 0, 0, 255, 0, 0, 255, 128]).unwrap();
+        let png = w.finish().unwrap();
+
+        let chunks = read_chunks(&png);
+        let kinds: Vec<&str> = chunks.iter().map(|(k, _)| k.as_str()).collect();
+        assert_eq!(vec!["IHDR", "IDAT", "IEND"], kinds);
+        assert_eq!(vec![0, 0, 0, 2, 0, 0, 0, 1, 8, 6, 0, 0, 0], chunks[0].1);
+        assert_eq!(
+            vec![0, 255, 0, 0, 255, 0, 0, 255, 128],
+            read_image_data(&png)
+        );
+    }
+
+    #[test]
+    fn test_multiple_blocks() {
+        // Each row is 3001 bytes with the filter type, 30 of them don't fit
+        // in one stored block
+        let (width, height) = (1000, 30);
+        let mut w = PngWriter::new(Vec::new(), width, height, ColorType::Rgb).unwrap();
+        let mut expected = Vec::new();
+        for y in 0..height {
+            let row: Vec<u8> = (0..width * 3).map(|i| (i + y) as u8).collect();
+            w.write_row(&row).unwrap();
+            expected.push(0);
+            expected.extend_from_slice(&row);
+        }
+        let png = w.finish().unwrap();
+        let idats = read_chunks(&png)
+            .iter()
+            .filter(|(kind, _)| kind == "IDAT")
+            .count();
+        assert_eq!(2, idats);
+        assert_eq!(expected, read_image_data(&png));
+    }
+
+    #[test]
+    fn test_invalid_use() {
+        assert!(PngWriter::new(Vec::new(), 0, 1, ColorType::Rgb).is_err());
+
+        let mut w = PngWriter::new(Vec::new(), 1, 1, ColorType::Rgb).unwrap();
+        assert!(w.write_row(&[1, 2]).is_err());
+        w.write_row(&[1, 2, 3]).unwrap();
+        assert!(w.write_row(&[1, 2, 3]).is_err());
+
+        let w = PngWriter::new(Vec::new(), 1, 2, ColorType::Rgb).unwrap();
+        assert!(w.finish().is_err());
+    }
+}
@@ -0,0 +1,313 @@
+use std::sync::Arc;
+
+use crate::{
+    bounds::BoundingBox,
+    intersections::{Intersectable, Intersection},
+    materials::Material,
+    matrix::{Matrix, MatrixError},
+    rays::Ray,
+    tuple::{Point, Vector},
+};
+
+/// Where a shape sits in the world: its own transform, the cached inverse
+/// and the placement of the group containing it (if any). Patterns use it
+/// for their transform too, without a parent.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Placement {
+    transform: Matrix<f64>,
+    // None when the transform is not invertible
+    inverse: Option<Matrix<f64>>,
+    parent: Option<Arc<Placement>>,
+}
+
+impl Default for Placement {
+    fn default() -> Self {
+        Placement::new(Matrix::identity(4, 1.0))
+    }
+}
+
+impl Placement {
+    pub fn new(transform: Matrix<f64>) -> Self {
+        let inverse = transform.inverse().ok();
+        Placement {
+            transform,
+            inverse,
+            parent: None,
+        }
+    }
+
+    pub fn transform(&self) -> &Matrix<f64> {
+        &self.transform
+    }
+
+    pub fn set_transform(&mut self, m: Matrix<f64>) {
+        self.inverse = m.inverse().ok();
+        self.transform = m;
+    }
+
+    pub fn inverse(&self) -> Result<&Matrix<f64>, MatrixError> {
+        self.inverse.as_ref().ok_or_else(|| {
+            MatrixError::InvalidArgument(String::from("Transform is not invertible"))
+        })
+    }
+
+    pub fn parent(&self) -> Option<&Placement> {
+        self.parent.as_deref()
+    }
+
+    pub fn set_parent(&mut self, parent: Option<Arc<Placement>>) {
+        self.parent = parent;
+    }
+
+    /// Convert a world space point to object space, going through every parent
+    pub fn world_to_object(&self, p: Point) -> Result<Point, MatrixError> {
+        let p = match self.parent() {
+            Some(parent) => parent.world_to_object(p)?,
+            None => p,
+        };
+        self.inverse()? * p
+    }
+
+    /// Convert an object space normal to world space, going through every parent
+    pub fn normal_to_world(&self, n: Vector) -> Result<Vector, MatrixError> {
+        let n = (&self.inverse()?.transpose() * n)?.normalize();
+        match self.parent() {
+            Some(parent) => parent.normal_to_world(n),
+            None => Ok(n),
+        }
+    }
+}
+
+/// A primitive defined in its own object space.
+/// Implementors only deal with untransformed geometry, the default methods
+/// take care of moving rays, points and normals between world and object space.
+pub trait Shape {
+    fn placement(&self) -> &Placement;
+
+    fn placement_mut(&mut self) -> &mut Placement;
+
+    fn material(&self) -> &Material;
+
+    fn material_mut(&mut self) -> &mut Material;
+
+    /// Intersect with a ray already transformed to object space
+    fn local_intersect(&self, r: Ray) -> Vec<Intersection<'_, Self>>
+    where
+        Self: Sized;
+
+    /// Normal at a point in object space
+    fn local_normal_at(&self, p: Point) -> Vector;
+
+    /// Normal at a point in object space, for a hit at barycentric `u`/`v`.
+    /// Only shapes interpolating their normals need to override this.
+    fn local_normal_at_uv(&self, p: Point, _u: f64, _v: f64) -> Vector {
+        self.local_normal_at(p)
+    }
+
+    /// Bounds of the shape in object space
+    fn bounds(&self) -> BoundingBox;
+
+    /// Bounds of the shape once its transform is applied
+    fn parent_space_bounds(&self) -> BoundingBox {
+        self.bounds().transform(self.transform())
+    }
+
+    fn transform(&self) -> &Matrix<f64> {
+        self.placement().transform()
+    }
+
+    fn set_transform(&mut self, m: Matrix<f64>) {
+        self.placement_mut().set_transform(m);
+    }
+
+    fn parent(&self) -> Option<&Placement> {
+        self.placement().parent()
+    }
+
+    fn world_to_object(&self, p: Point) -> Result<Point, MatrixError> {
+        self.placement().world_to_object(p)
+    }
+
+    fn normal_to_world(&self, n: Vector) -> Result<Vector, MatrixError> {
+        self.placement().normal_to_world(n)
+    }
+
+    fn normal_at(&self, p: Point) -> Result<Vector, MatrixError> {
+        let local_point = self.world_to_object(p)?;
+        let local_normal = self.local_normal_at(local_point);
+        self.normal_to_world(local_normal)
+    }
+
+    fn normal_at_uv(&self, p: Point, u: f64, v: f64) -> Result<Vector, MatrixError> {
+        let local_point = self.world_to_object(p)?;
+        let local_normal = self.local_normal_at_uv(local_point, u, v);
+        self.normal_to_world(local_normal)
+    }
+}
+
+impl<T> Intersectable for T
+where
+    T: Shape,
+{
+    fn intersect(&self, r: Ray) -> Result<Vec<Intersection<'_, Self>>, MatrixError> {
+        let local_ray = r.transform(self.placement().inverse()?)?;
+        Ok(self.local_intersect(local_ray))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::{FRAC_1_SQRT_2, FRAC_PI_2};
+
+    use crate::{
+        matrix::Axis,
+        tuple::{IsTuple, Point, Vector},
+    };
+
+    use super::*;
+
+    // Minimal shape recording the ray it was intersected with
+    #[derive(Default)]
+    struct TestShape {
+        placement: Placement,
+        material: Material,
+        saved_ray: std::cell::Cell<Option<Ray>>,
+    }
+
+    impl Shape for TestShape {
+        fn placement(&self) -> &Placement {
+            &self.placement
+        }
+
+        fn placement_mut(&mut self) -> &mut Placement {
+            &mut self.placement
+        }
+
+        fn material(&self) -> &Material {
+            &self.material
+        }
+
+        fn material_mut(&mut self) -> &mut Material {
+            &mut self.material
+        }
+
+        fn local_intersect(&self, r: Ray) -> Vec<Intersection<'_, Self>> {
+            self.saved_ray.set(Some(r));
+            Vec::new()
+        }
+
+        fn local_normal_at(&self, p: Point) -> Vector {
+            Vector::new(p.0.x, p.0.y, p.0.z)
+        }
+
+        fn bounds(&self) -> BoundingBox {
+            BoundingBox::new(Point::new(-1.0, -1.0, -1.0), Point::new(1.0, 1.0, 1.0))
+        }
+    }
+
+    #[test]
+    fn test_default_transform() {
+        let s = TestShape::default();
+        assert_eq!(&Matrix::identity(4, 1.0), s.transform());
+        assert!(s.parent().is_none());
+    }
+
+    #[test]
+    fn test_set_transform() {
+        let mut s = TestShape::default();
+        s.set_transform(Matrix::translation(2.0, 3.0, 4.0));
+        assert_eq!(&Matrix::translation(2.0, 3.0, 4.0), s.transform());
+    }
+
+    #[test]
+    fn test_material() {
+        let mut s = TestShape::default();
+        assert_eq!(&Material::default(), s.material());
+        s.material_mut().ambient = 1.0;
+        assert_eq!(1.0, s.material().ambient);
+    }
+
+    #[test]
+    fn test_intersect_transforms_ray() {
+        let r = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        let mut s = TestShape::default();
+
+        s.set_transform(Matrix::scaling(2.0, 2.0, 2.0));
+        s.intersect(r).unwrap();
+        let saved = s.saved_ray.get().unwrap();
+        assert_eq!(Point::new(0.0, 0.0, -2.5), saved.origin);
+        assert_eq!(Vector::new(0.0, 0.0, 0.5), saved.direction);
+
+        s.set_transform(Matrix::translation(5.0, 0.0, 0.0));
+        s.intersect(r).unwrap();
+        let saved = s.saved_ray.get().unwrap();
+        assert_eq!(Point::new(-5.0, 0.0, -5.0), saved.origin);
+        assert_eq!(Vector::new(0.0, 0.0, 1.0), saved.direction);
+    }
+
+    #[test]
+    fn test_intersect_non_invertible_transform() {
+        let r = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        let mut s = TestShape::default();
+        s.set_transform(Matrix::scaling(0.0, 1.0, 1.0));
+        assert!(s.intersect(r).is_err());
+    }
+
+    #[test]
+    fn test_normal_at_transformed() {
+        let mut s = TestShape::default();
+        s.set_transform(Matrix::translation(0.0, 1.0, 0.0));
+        let n = s
+            .normal_at(Point::new(0.0, 1.0 + FRAC_1_SQRT_2, -FRAC_1_SQRT_2))
+            .unwrap();
+        assert_eq!(
+            Vector::new(0.0, FRAC_1_SQRT_2, -FRAC_1_SQRT_2).limit_precision(5),
+            n.limit_precision(5)
+        );
+
+        s.set_transform(
+            Matrix::rotation(Axis::Z, std::f64::consts::PI / 5.0)
+                .scale(1.0, 0.5, 1.0)
+                .unwrap(),
+        );
+        let n = s
+            .normal_at(Point::new(0.0, FRAC_1_SQRT_2, -FRAC_1_SQRT_2))
+            .unwrap();
+        assert_eq!(Vector::new(0.0, 0.97014, -0.24254), n.limit_precision(5));
+    }
+
+    #[test]
+    fn test_world_to_object_through_parents() {
+        let outer = Placement::new(Matrix::rotation(Axis::Y, FRAC_PI_2));
+        let inner = Placement::new(Matrix::scaling(2.0, 2.0, 2.0));
+        let mut s = TestShape::default();
+        s.placement_mut().set_parent(Some(Arc::new(Placement {
+            parent: Some(Arc::new(outer)),
+            ..inner
+        })));
+        s.set_transform(Matrix::translation(5.0, 0.0, 0.0));
+        let p = s
+            .placement()
+            .world_to_object(Point::new(-2.0, 0.0, -10.0))
+            .unwrap();
+        assert_eq!(Point::new(0.0, 0.0, -1.0), p.limit_precision(5));
+    }
+
+    #[test]
+    fn test_normal_to_world_through_parents() {
+        let outer = Placement::new(Matrix::rotation(Axis::Y, FRAC_PI_2));
+        let inner = Placement::new(Matrix::scaling(1.0, 2.0, 3.0));
+        let mut s = TestShape::default();
+        s.placement_mut().set_parent(Some(Arc::new(Placement {
+            parent: Some(Arc::new(outer)),
+            ..inner
+        })));
+        s.set_transform(Matrix::translation(5.0, 0.0, 0.0));
+        let third = 3.0_f64.sqrt() / 3.0;
+        let n = s
+            .placement()
+            .normal_to_world(Vector::new(third, third, third))
+            .unwrap();
+        assert_eq!(Vector::new(0.2857, 0.4286, -0.8571), n.limit_precision(4));
+    }
+}
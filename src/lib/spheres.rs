@@ -1,10 +1,11 @@
 use std::ops::Neg;
 
 use crate::{
-    intersections::{Intersectable, Intersection},
+    bounds::BoundingBox,
+    intersections::Intersection,
     materials::Material,
-    matrix::{Matrix, MatrixError},
     rays::Ray,
+    shapes::{Placement, Shape},
     tuple::{IsTuple, Point, Vector},
 };
 
@@ -12,51 +13,48 @@ use crate::{
 pub struct Sphere {
     origin: Point,
     radius: f64,
-    transform: Matrix<f64>,
+    placement: Placement,
     pub material: Material,
 }
 
 impl Default for Sphere {
     fn default() -> Self {
-        Sphere {
-            origin: Point::new(0.0, 0.0, 0.0),
-            radius: 1.0,
-            transform: Matrix::identity(4, 1.0),
-            material: Material::default(),
-        }
+        Sphere::new(Point::new(0.0, 0.0, 0.0), 1.0)
     }
 }
 
 impl Sphere {
     pub fn new(origin: Point, radius: f64) -> Self {
-        let m = Matrix::identity(4, 1.0);
         Sphere {
             origin,
             radius,
-            transform: m,
+            placement: Placement::default(),
             material: Material::default(),
         }
     }
+}
 
-    pub fn set_transform(&mut self, m: Matrix<f64>) {
-        self.transform = m;
+impl Shape for Sphere {
+    fn placement(&self) -> &Placement {
+        &self.placement
     }
 
-    pub fn normal_at(&self, p: Point) -> Result<Vector, MatrixError> {
-        let object_point = (&self.transform.inverse()? * p)?;
-        let object_normal = object_point - self.origin;
-        let mut world_normal = (&self.transform.inverse()?.transpose() * object_normal)?;
-        world_normal.0.w = 0.0;
-        Ok(world_normal.normalize())
+    fn placement_mut(&mut self) -> &mut Placement {
+        &mut self.placement
+    }
+
+    fn material(&self) -> &Material {
+        &self.material
+    }
+
+    fn material_mut(&mut self) -> &mut Material {
+        &mut self.material
     }
-}
 
-impl Intersectable for Sphere {
-    fn intersect(&self, r: Ray) -> Result<Vec<Intersection<Sphere>>, MatrixError> {
-        let r2 = r.transform(&self.transform.inverse()?)?;
-        let sphere_to_ray = r2.origin - self.origin;
-        let a = r2.direction.dot(r2.direction);
-        let b = 2.0 * r2.direction.dot(sphere_to_ray);
+    fn local_intersect(&self, r: Ray) -> Vec<Intersection<'_, Sphere>> {
+        let sphere_to_ray = r.origin - self.origin;
+        let a = r.direction.dot(r.direction);
+        let b = 2.0 * r.direction.dot(sphere_to_ray);
         let c = sphere_to_ray.dot(sphere_to_ray) - 1.0;
         let discriminant = b.powf(2.0) - 4.0 * a * c;
 
@@ -71,21 +69,34 @@ impl Intersectable for Sphere {
                 self,
             ));
         }
-        Ok(intersections)
+        intersections
+    }
+
+    fn local_normal_at(&self, p: Point) -> Vector {
+        p - self.origin
+    }
+
+    fn bounds(&self) -> BoundingBox {
+        let r = self.radius;
+        BoundingBox::new(
+            self.origin + Vector::new(-r, -r, -r),
+            self.origin + Vector::new(r, r, r),
+        )
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::matrix::Axis;
-    use crate::tuple::IsTuple;
+    use std::f64::consts::FRAC_1_SQRT_2;
+
+    use crate::matrix::{Axis, Matrix};
 
     use super::*;
 
     #[test]
     fn test_sphere_default_transformation() {
         let s = Sphere::new(Point::new(0.0, 0.0, 0.0), 1.0);
-        assert_eq!(Matrix::identity(4, 1.0), s.transform);
+        assert_eq!(&Matrix::identity(4, 1.0), s.transform());
     }
 
     #[test]
@@ -134,9 +145,11 @@ mod tests {
     fn test_sphere_normal_with_transformations() {
         let mut s = Sphere::new(Point::new(0.0, 0.0, 0.0), 1.0);
         s.set_transform(Matrix::translation(0.0, 1.0, 0.0));
-        let n1 = s.normal_at(Point::new(0.0, 1.70711, -0.70711)).unwrap();
-        let e1 = Vector::new(0.0, 0.70711, -0.70711);
-        assert_eq!(e1, n1.limit_precision(5));
+        let n1 = s
+            .normal_at(Point::new(0.0, 1.0 + FRAC_1_SQRT_2, -FRAC_1_SQRT_2))
+            .unwrap();
+        let e1 = Vector::new(0.0, FRAC_1_SQRT_2, -FRAC_1_SQRT_2);
+        assert_eq!(e1.limit_precision(5), n1.limit_precision(5));
 
         s.set_transform(
             Matrix::rotation(Axis::Z, std::f64::consts::PI / 5.0)
@@ -153,4 +166,12 @@ mod tests {
         let e2 = Vector::new(0.0, 0.97014, -0.24254);
         assert_eq!(e2, n2.limit_precision(5));
     }
+
+    #[test]
+    fn test_bounds() {
+        let s = Sphere::new(Point::new(1.0, 0.0, 0.0), 2.0);
+        let b = s.bounds();
+        assert_eq!(Point::new(-1.0, -2.0, -2.0), b.min);
+        assert_eq!(Point::new(3.0, 2.0, 2.0), b.max);
+    }
 }
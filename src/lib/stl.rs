@@ -0,0 +1,391 @@
+use std::collections::HashMap;
+
+use thiserror::Error;
+
+use crate::{
+    groups::Group,
+    objects::Object,
+    triangles::{SmoothTriangle, Triangle},
+    tuple::{IsTuple, Point, Vector},
+    utils::EPSILON,
+};
+
+#[derive(Error, Debug, PartialEq)]
+pub enum StlError {
+    #[error("Line {line}: {reason}")]
+    Malformed { line: usize, reason: String },
+    #[error("Unexpected end of data")]
+    UnexpectedEnd,
+}
+
+/// Single STL triangle with the normal stored alongside it in the file
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Facet {
+    pub normal: Vector,
+    pub vertices: [Point; 3],
+}
+
+impl Facet {
+    // Triangle normals follow the order of the vertices, swap two of them if
+    // that would point it away from the stored normal. Files often leave the
+    // stored normal zeroed, in which case the order is kept.
+    fn oriented(&self) -> [Point; 3] {
+        let [p1, p2, p3] = self.vertices;
+        let normal = (p3 - p1).cross(p2 - p1);
+        if normal.dot(self.normal) < 0.0 {
+            [p1, p3, p2]
+        } else {
+            [p1, p2, p3]
+        }
+    }
+}
+
+/// STL mesh read from either an ascii or a binary file
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct StlFile {
+    facets: Vec<Facet>,
+}
+
+impl StlFile {
+    pub fn parse(bytes: &[u8]) -> Result<Self, StlError> {
+        // Binary files may start with "solid" too, but their size is fully
+        // determined by the triangle count after the 80 byte header
+        if bytes.len() >= 84 {
+            let count = u32::from_le_bytes([bytes[80], bytes[81], bytes[82], bytes[83]]) as usize;
+            if count.checked_mul(50) == Some(bytes.len() - 84) {
+                return StlFile::parse_binary(bytes);
+            }
+        }
+        // Anything that isn't text is taken to be a binary file of the wrong
+        // size, which reports the truncation rather than an encoding error
+        match std::str::from_utf8(bytes) {
+            Ok(content) if content.starts_with("solid") => StlFile::parse_ascii(content),
+            _ => StlFile::parse_binary(bytes),
+        }
+    }
+
+    fn parse_binary(bytes: &[u8]) -> Result<Self, StlError> {
+        let count = match bytes.get(80..84) {
+            Some(b) => u32::from_le_bytes([b[0], b[1], b[2], b[3]]) as usize,
+            None => return Err(StlError::UnexpectedEnd),
+        };
+        // Each facet is 12 floats followed by a 2 byte attribute count.
+        // Check the count against the data before trusting it for allocation.
+        if (bytes.len() - 84) / 50 < count {
+            return Err(StlError::UnexpectedEnd);
+        }
+        let mut facets = Vec::with_capacity(count);
+        for chunk in bytes[84..].chunks_exact(50).take(count) {
+            let f = |i: usize| {
+                let b = &chunk[i * 4..i * 4 + 4];
+                f32::from_le_bytes([b[0], b[1], b[2], b[3]]) as f64
+            };
+            facets.push(Facet {
+                normal: Vector::new(f(0), f(1), f(2)),
+                vertices: [
+                    Point::new(f(3), f(4), f(5)),
+                    Point::new(f(6), f(7), f(8)),
+                    Point::new(f(9), f(10), f(11)),
+                ],
+            });
+        }
+        Ok(StlFile { facets })
+    }
+
+    fn parse_ascii(content: &str) -> Result<Self, StlError> {
+        let mut facets = Vec::new();
+        let mut normal = None;
+        let mut vertices = Vec::with_capacity(3);
+
+        for (i, line) in content.lines().enumerate() {
+            let line_num = i + 1;
+            let tokens: Vec<&str> = line.split_whitespace().collect();
+            let malformed = |reason: &str| StlError::Malformed {
+                line: line_num,
+                reason: reason.to_string(),
+            };
+
+            match tokens.as_slice() {
+                ["solid", ..] | ["endsolid", ..] | ["outer", "loop"] | ["endloop"] | [] => {}
+                ["facet", "normal", args @ ..] => {
+                    if normal.is_some() {
+                        return Err(malformed("facet inside another facet"));
+                    }
+                    let [x, y, z] = parse_floats(args, line_num)?;
+                    normal = Some(Vector::new(x, y, z));
+                }
+                ["vertex", args @ ..] => {
+                    if normal.is_none() {
+                        return Err(malformed("vertex outside a facet"));
+                    }
+                    let [x, y, z] = parse_floats(args, line_num)?;
+                    vertices.push(Point::new(x, y, z));
+                }
+                ["endfacet"] => {
+                    let normal = normal
+                        .take()
+                        .ok_or_else(|| malformed("endfacet without facet"))?;
+                    if vertices.len() != 3 {
+                        return Err(malformed(&format!(
+                            "facet needs 3 vertices, got {}",
+                            vertices.len()
+                        )));
+                    }
+                    facets.push(Facet {
+                        normal,
+                        vertices: [vertices[0], vertices[1], vertices[2]],
+                    });
+                    vertices.clear();
+                }
+                _ => return Err(malformed(&format!("unexpected {:?}", line.trim()))),
+            }
+        }
+
+        if normal.is_some() {
+            return Err(StlError::UnexpectedEnd);
+        }
+        Ok(StlFile { facets })
+    }
+
+    pub fn facets(&self) -> &[Facet] {
+        &self.facets
+    }
+
+    /// Group of flat triangles, one per facet
+    pub fn into_group(self) -> Group {
+        let mut mesh = Group::new();
+        for facet in self.facets.iter() {
+            let [p1, p2, p3] = facet.oriented();
+            mesh.add_child(Triangle::new(p1, p2, p3));
+        }
+        mesh
+    }
+
+    /// Group of smooth triangles. Facets sharing a vertex are welded
+    /// together, and the normal at the vertex is the average of the normals
+    /// of the facets around it. Facets at more than `crease_angle` radians
+    /// from each other don't affect each other's normals, which keeps hard
+    /// edges sharp.
+    pub fn into_smooth_group(self, crease_angle: f64) -> Group {
+        let triangles: Vec<([Point; 3], Vector)> = self
+            .facets
+            .iter()
+            .map(|f| {
+                let [p1, p2, p3] = f.oriented();
+                ([p1, p2, p3], (p3 - p1).cross(p2 - p1))
+            })
+            .collect();
+
+        // Facets touching each welded vertex
+        let mut shared: HashMap<(i64, i64, i64), Vec<usize>> = HashMap::new();
+        for (i, (points, _)) in triangles.iter().enumerate() {
+            for p in points {
+                shared.entry(weld_key(*p)).or_default().push(i);
+            }
+        }
+
+        let min_cos = crease_angle.cos();
+        let mut mesh = Group::new();
+        for (points, face_normal) in triangles.iter() {
+            let face_dir = face_normal.normalize();
+            let vertex_normal = |p: Point| {
+                // Larger facets weigh more as their normals aren't normalized
+                let mut sum = Vector::new(0.0, 0.0, 0.0);
+                for &j in shared[&weld_key(p)].iter() {
+                    let other = triangles[j].1;
+                    if other.normalize().dot(face_dir) >= min_cos - EPSILON {
+                        sum = sum + other;
+                    }
+                }
+                sum.normalize()
+            };
+            let [p1, p2, p3] = *points;
+            mesh.add_child(SmoothTriangle::new(
+                p1,
+                p2,
+                p3,
+                vertex_normal(p1),
+                vertex_normal(p2),
+                vertex_normal(p3),
+            ));
+        }
+        mesh
+    }
+}
+
+impl From<StlFile> for Object {
+    fn from(stl: StlFile) -> Self {
+        stl.into_group().into()
+    }
+}
+
+/// Vertices this close to each other are treated as the same one
+fn weld_key(p: Point) -> (i64, i64, i64) {
+    let q = |x: f64| (x / EPSILON).round() as i64;
+    (q(p.0.x), q(p.0.y), q(p.0.z))
+}
+
+fn parse_floats(args: &[&str], line: usize) -> Result<[f64; 3], StlError> {
+    if args.len() != 3 {
+        return Err(StlError::Malformed {
+            line,
+            reason: format!("expected 3 numbers, got {}", args.len()),
+        });
+    }
+    let mut out = [0.0; 3];
+    for (value, arg) in out.iter_mut().zip(args) {
+        *value = arg.parse().map_err(|_| StlError::Malformed {
+            line,
+            reason: format!("invalid number {:?}", arg),
+        })?;
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::FRAC_PI_4;
+
+    use super::*;
+
+    // Two facets of a unit square folded 90 degrees along the x axis
+    const FOLDED: &str = "solid folded\n\
+                          facet normal 0 0 -1\n\
+                          outer loop\n\
+                          vertex 0 0 0\n\
+                          vertex 0 1 0\n\
+                          vertex 1 0 0\n\
+                          endloop\n\
+                          endfacet\n\
+                          facet normal 0 -1 0\n\
+                          outer loop\n\
+                          vertex 0 0 0\n\
+                          vertex 1 0 0\n\
+                          vertex 0 0 1\n\
+                          endloop\n\
+                          endfacet\n\
+                          endsolid folded\n";
+
+    fn binary(facets: &[Facet]) -> Vec<u8> {
+        // Header deliberately starts like an ascii file
+        let mut bytes = b"solid but actually binary".to_vec();
+        bytes.resize(80, 0);
+        bytes.extend_from_slice(&(facets.len() as u32).to_le_bytes());
+        for f in facets {
+            let [p1, p2, p3] = f.vertices;
+            for t in [f.normal.0, p1.0, p2.0, p3.0] {
+                for x in [t.x, t.y, t.z] {
+                    bytes.extend_from_slice(&(x as f32).to_le_bytes());
+                }
+            }
+            bytes.extend_from_slice(&[0, 0]);
+        }
+        bytes
+    }
+
+    #[test]
+    fn test_ascii() {
+        let stl = StlFile::parse(FOLDED.as_bytes()).unwrap();
+        assert_eq!(2, stl.facets().len());
+        assert_eq!(Vector::new(0.0, -1.0, 0.0), stl.facets()[1].normal);
+        assert_eq!(Point::new(0.0, 0.0, 1.0), stl.facets()[1].vertices[2]);
+    }
+
+    #[test]
+    fn test_binary() {
+        let ascii = StlFile::parse(FOLDED.as_bytes()).unwrap();
+        let bytes = binary(ascii.facets());
+        assert_eq!(ascii, StlFile::parse(&bytes).unwrap());
+        assert_eq!(
+            Err(StlError::UnexpectedEnd),
+            StlFile::parse_binary(&bytes[..bytes.len() - 1])
+        );
+    }
+
+    #[test]
+    fn test_binary_huge_count() {
+        // A short file claiming billions of facets fails instead of trying
+        // to allocate room for them
+        let mut bytes = vec![0; 100];
+        bytes[80..84].copy_from_slice(&u32::MAX.to_le_bytes());
+        assert_eq!(Err(StlError::UnexpectedEnd), StlFile::parse(&bytes));
+    }
+
+    #[test]
+    fn test_binary_truncated_with_solid_header() {
+        // The header starts with "solid", a byte that can't be utf-8 makes
+        // sure the data isn't mistaken for text
+        let ascii = StlFile::parse(FOLDED.as_bytes()).unwrap();
+        let mut bytes = binary(ascii.facets());
+        bytes[84] = 0xff;
+        assert_eq!(
+            ascii.facets().len(),
+            StlFile::parse(&bytes).unwrap().facets().len()
+        );
+        assert_eq!(
+            Err(StlError::UnexpectedEnd),
+            StlFile::parse(&bytes[..bytes.len() - 10])
+        );
+    }
+
+    #[test]
+    fn test_malformed() {
+        let cases = [
+            (FOLDED.replace("vertex 0 1 0", "vertex 0 one 0"), 5),
+            (FOLDED.replace("vertex 0 1 0", "vertex 0 1"), 5),
+            (FOLDED.replace("vertex 0 1 0\n", ""), 7),
+            (FOLDED.replace("endloop", "end loop"), 7),
+        ];
+        for (content, line) in cases {
+            match StlFile::parse(content.as_bytes()) {
+                Err(StlError::Malformed { line: l, .. }) => assert_eq!(line, l),
+                other => panic!("Expected a malformed error, got {:?}", other),
+            }
+        }
+        let truncated = &FOLDED[..FOLDED.find("endfacet").unwrap()];
+        assert_eq!(
+            Err(StlError::UnexpectedEnd),
+            StlFile::parse(truncated.as_bytes())
+        );
+    }
+
+    #[test]
+    fn test_into_group_orients_to_stored_normal() {
+        let g = StlFile::parse(FOLDED.as_bytes()).unwrap().into_group();
+        assert_eq!(2, g.children().len());
+        let expected = [Vector::new(0.0, 0.0, -1.0), Vector::new(0.0, -1.0, 0.0)];
+        for (child, normal) in g.children().iter().zip(expected) {
+            match child {
+                Object::Triangle(t) => assert_eq!(normal, t.normal()),
+                _ => panic!("Expected a triangle"),
+            }
+        }
+    }
+
+    #[test]
+    fn test_smooth_group() {
+        let stl = StlFile::parse(FOLDED.as_bytes()).unwrap();
+        let g = stl.clone().into_smooth_group(FRAC_PI_4 * 3.0);
+        assert_eq!(2, g.children().len());
+        match &g.children()[0] {
+            Object::SmoothTriangle(t) => {
+                // Shared edge averages both facets, the far corner is flat
+                let n = Vector::new(0.0, -1.0, -1.0).normalize();
+                assert_eq!(n, t.n1());
+                assert_eq!(n, t.n2());
+                assert_eq!(Vector::new(0.0, 0.0, -1.0), t.n3());
+            }
+            _ => panic!("Expected a smooth triangle"),
+        }
+
+        // Fold is sharper than the crease angle so the edge stays hard
+        let g = stl.into_smooth_group(FRAC_PI_4);
+        match &g.children()[0] {
+            Object::SmoothTriangle(t) => {
+                assert_eq!(Vector::new(0.0, 0.0, -1.0), t.n1());
+                assert_eq!(Vector::new(0.0, 0.0, -1.0), t.n2());
+            }
+            _ => panic!("Expected a smooth triangle"),
+        }
+    }
+}
@@ -0,0 +1,280 @@
+use std::{f64::consts::PI, sync::Arc};
+
+use crate::{
+    canvas::Canvas,
+    color::{Color, CommonColor},
+    tuple::Point,
+};
+
+/// How points in pattern space are projected onto the 2D texture.
+/// `u` runs left to right and `v` bottom to top, both within [0, 1].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UvMapping {
+    /// Wraps the texture around a unit sphere, like a globe
+    Spherical,
+    /// Tiles the texture over the xz plane, one unit per copy
+    Planar,
+    /// Wraps the texture around the y axis, repeating every unit of height
+    Cylindrical,
+    /// Puts a full copy of the texture on each face of a unit cube
+    Cube,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CubeFace {
+    Left,
+    Right,
+    Front,
+    Back,
+    Up,
+    Down,
+}
+
+impl UvMapping {
+    pub fn map(&self, p: Point) -> (f64, f64) {
+        let (x, y, z) = (p.0.x, p.0.y, p.0.z);
+        match self {
+            UvMapping::Spherical => {
+                let radius = (x * x + y * y + z * z).sqrt();
+                if radius == 0.0 {
+                    return (0.5, 0.5);
+                }
+                let phi = (y / radius).acos();
+                (azimuth(x, z), 1.0 - phi / PI)
+            }
+            UvMapping::Planar => (x.rem_euclid(1.0), z.rem_euclid(1.0)),
+            UvMapping::Cylindrical => (azimuth(x, z), y.rem_euclid(1.0)),
+            UvMapping::Cube => {
+                let wrap = |t: f64| t.rem_euclid(2.0) / 2.0;
+                match cube_face(x, y, z) {
+                    CubeFace::Front => (wrap(x + 1.0), wrap(y + 1.0)),
+                    CubeFace::Back => (wrap(1.0 - x), wrap(y + 1.0)),
+                    CubeFace::Left => (wrap(z + 1.0), wrap(y + 1.0)),
+                    CubeFace::Right => (wrap(1.0 - z), wrap(y + 1.0)),
+                    CubeFace::Up => (wrap(x + 1.0), wrap(1.0 - z)),
+                    CubeFace::Down => (wrap(x + 1.0), wrap(z + 1.0)),
+                }
+            }
+        }
+    }
+}
+
+// Angle around the y axis as a fraction of a turn, increasing
+// counter-clockwise when seen from above
+fn azimuth(x: f64, z: f64) -> f64 {
+    let raw_u = x.atan2(z) / (2.0 * PI);
+    1.0 - (raw_u + 0.5)
+}
+
+fn cube_face(x: f64, y: f64, z: f64) -> CubeFace {
+    let coord = x.abs().max(y.abs()).max(z.abs());
+    if coord == x {
+        CubeFace::Right
+    } else if coord == -x {
+        CubeFace::Left
+    } else if coord == y {
+        CubeFace::Up
+    } else if coord == -y {
+        CubeFace::Down
+    } else if coord == z {
+        CubeFace::Front
+    } else {
+        CubeFace::Back
+    }
+}
+
+/// Image mapped onto a surface. The image is shared between clones so that
+/// materials using it stay cheap to copy around.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Texture {
+    image: Arc<Canvas>,
+    mapping: UvMapping,
+}
+
+impl Texture {
+    pub fn new(image: Canvas, mapping: UvMapping) -> Self {
+        Texture {
+            image: Arc::new(image),
+            mapping,
+        }
+    }
+
+    pub fn image(&self) -> &Canvas {
+        &self.image
+    }
+
+    pub fn mapping(&self) -> UvMapping {
+        self.mapping
+    }
+
+    /// Color at a point in pattern space
+    pub fn color_at(&self, p: Point) -> Color {
+        let (u, v) = self.mapping.map(p);
+        self.uv_color_at(u, v)
+    }
+
+    /// Bilinear interpolation of the four pixels around `u`/`v`.
+    /// Pixel centers sit on the image edges, so `(0, 0)` is exactly the
+    /// bottom left pixel.
+    pub fn uv_color_at(&self, u: f64, v: f64) -> Color {
+        let (width, height) = (self.image.width(), self.image.height());
+        if width == 0 || height == 0 {
+            return CommonColor::Black.value();
+        }
+        let x = u.clamp(0.0, 1.0) * (width - 1) as f64;
+        let y = (1.0 - v.clamp(0.0, 1.0)) * (height - 1) as f64;
+        let (x0, y0) = (x.floor() as usize, y.floor() as usize);
+        let (x1, y1) = ((x0 + 1).min(width - 1), (y0 + 1).min(height - 1));
+        let (tx, ty) = (x - x0 as f64, y - y0 as f64);
+
+        let top = self.image.pixel_at(x0, y0) * (1.0 - tx) + self.image.pixel_at(x1, y0) * tx;
+        let bottom = self.image.pixel_at(x0, y1) * (1.0 - tx) + self.image.pixel_at(x1, y1) * tx;
+        top * (1.0 - ty) + bottom * ty
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f64::consts::FRAC_1_SQRT_2;
+
+    use crate::tuple::IsTuple;
+
+    use super::*;
+
+    fn assert_uv(expected: (f64, f64), actual: (f64, f64)) {
+        assert!(
+            (expected.0 - actual.0).abs() < 1e-5 && (expected.1 - actual.1).abs() < 1e-5,
+            "expected {:?}, got {:?}",
+            expected,
+            actual
+        );
+    }
+
+    #[test]
+    fn test_spherical() {
+        let cases = [
+            ([0.0, 0.0, -1.0], (0.0, 0.5)),
+            ([1.0, 0.0, 0.0], (0.25, 0.5)),
+            ([0.0, 0.0, 1.0], (0.5, 0.5)),
+            ([-1.0, 0.0, 0.0], (0.75, 0.5)),
+            ([0.0, 1.0, 0.0], (0.5, 1.0)),
+            ([0.0, -1.0, 0.0], (0.5, 0.0)),
+            ([FRAC_1_SQRT_2, FRAC_1_SQRT_2, 0.0], (0.25, 0.75)),
+        ];
+        for (p, uv) in cases {
+            assert_uv(uv, UvMapping::Spherical.map(Point::from(p)));
+        }
+    }
+
+    #[test]
+    fn test_planar() {
+        let cases = [
+            ([0.25, 0.0, 0.5], (0.25, 0.5)),
+            ([0.25, 0.0, -0.25], (0.25, 0.75)),
+            ([0.25, 0.5, -0.25], (0.25, 0.75)),
+            ([1.25, 0.0, 0.5], (0.25, 0.5)),
+            ([0.25, 0.0, -1.75], (0.25, 0.25)),
+            ([1.0, 0.0, -1.0], (0.0, 0.0)),
+            ([0.0, 0.0, 0.0], (0.0, 0.0)),
+        ];
+        for (p, uv) in cases {
+            assert_uv(uv, UvMapping::Planar.map(Point::from(p)));
+        }
+    }
+
+    #[test]
+    fn test_cylindrical() {
+        let cases = [
+            ([0.0, 0.0, -1.0], (0.0, 0.0)),
+            ([0.0, 0.5, -1.0], (0.0, 0.5)),
+            ([0.0, 1.0, -1.0], (0.0, 0.0)),
+            ([FRAC_1_SQRT_2, 0.5, -FRAC_1_SQRT_2], (0.125, 0.5)),
+            ([1.0, 0.5, 0.0], (0.25, 0.5)),
+            ([FRAC_1_SQRT_2, 0.5, FRAC_1_SQRT_2], (0.375, 0.5)),
+            ([0.0, -0.25, 1.0], (0.5, 0.75)),
+            ([-FRAC_1_SQRT_2, 0.5, FRAC_1_SQRT_2], (0.625, 0.5)),
+            ([-1.0, 1.25, 0.0], (0.75, 0.25)),
+            ([-FRAC_1_SQRT_2, 0.5, -FRAC_1_SQRT_2], (0.875, 0.5)),
+        ];
+        for (p, uv) in cases {
+            assert_uv(uv, UvMapping::Cylindrical.map(Point::from(p)));
+        }
+    }
+
+    #[test]
+    fn test_cube_face() {
+        let cases = [
+            ([-1.0, 0.5, -0.25], CubeFace::Left),
+            ([1.1, -0.75, 0.8], CubeFace::Right),
+            ([0.1, 0.6, 0.9], CubeFace::Front),
+            ([-0.7, 0.0, -2.0], CubeFace::Back),
+            ([0.5, 1.0, 0.9], CubeFace::Up),
+            ([-0.2, -1.3, 1.1], CubeFace::Down),
+        ];
+        for ([x, y, z], face) in cases {
+            assert_eq!(face, cube_face(x, y, z));
+        }
+    }
+
+    #[test]
+    fn test_cube() {
+        let cases = [
+            // Front
+            ([-0.5, 0.5, 1.0], (0.25, 0.75)),
+            ([0.5, -0.5, 1.0], (0.75, 0.25)),
+            // Back
+            ([0.5, 0.5, -1.0], (0.25, 0.75)),
+            ([-0.5, -0.5, -1.0], (0.75, 0.25)),
+            // Left
+            ([-1.0, 0.5, -0.5], (0.25, 0.75)),
+            ([-1.0, -0.5, 0.5], (0.75, 0.25)),
+            // Right
+            ([1.0, 0.5, 0.5], (0.25, 0.75)),
+            ([1.0, -0.5, -0.5], (0.75, 0.25)),
+            // Up
+            ([-0.5, 1.0, -0.5], (0.25, 0.75)),
+            ([0.5, 1.0, 0.5], (0.75, 0.25)),
+            // Down
+            ([-0.5, -1.0, 0.5], (0.25, 0.75)),
+            ([0.5, -1.0, -0.5], (0.75, 0.25)),
+        ];
+        for (p, uv) in cases {
+            assert_uv(uv, UvMapping::Cube.map(Point::from(p)));
+        }
+    }
+
+    #[test]
+    fn test_uv_color_at() {
+        // 2x2 image: red and green on top, blue and white at the bottom
+        let mut image = Canvas::new(2, 2);
+        image.write_pixel(0, 0, Color::new(1.0, 0.0, 0.0));
+        image.write_pixel(1, 0, Color::new(0.0, 1.0, 0.0));
+        image.write_pixel(0, 1, Color::new(0.0, 0.0, 1.0));
+        image.write_pixel(1, 1, Color::new(1.0, 1.0, 1.0));
+        let t = Texture::new(image, UvMapping::Planar);
+
+        assert_eq!(Color::new(0.0, 0.0, 1.0), t.uv_color_at(0.0, 0.0));
+        assert_eq!(Color::new(1.0, 1.0, 1.0), t.uv_color_at(1.0, 0.0));
+        assert_eq!(Color::new(1.0, 0.0, 0.0), t.uv_color_at(0.0, 1.0));
+        assert_eq!(Color::new(0.0, 1.0, 0.0), t.uv_color_at(1.0, 1.0));
+        // Halfway along the bottom edge, then right in the middle
+        assert_eq!(Color::new(0.5, 0.5, 1.0), t.uv_color_at(0.5, 0.0));
+        assert_eq!(Color::new(0.5, 0.5, 0.5), t.uv_color_at(0.5, 0.5));
+    }
+
+    #[test]
+    fn test_color_at() {
+        let mut image = Canvas::new(3, 3);
+        image.write_pixel(1, 1, Color::new(1.0, 1.0, 1.0));
+        let t = Texture::new(image, UvMapping::Spherical);
+        // The middle of the image is on the equator, facing +z
+        assert_eq!(
+            Color::new(1.0, 1.0, 1.0),
+            t.color_at(Point::new(0.0, 0.0, 1.0))
+        );
+        assert_eq!(
+            Color::new(0.0, 0.0, 0.0),
+            t.color_at(Point::new(0.0, 1.0, 0.0))
+        );
+    }
+}
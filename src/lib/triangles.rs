@@ -0,0 +1,343 @@
+use crate::{
+    bounds::BoundingBox,
+    intersections::Intersection,
+    materials::Material,
+    rays::Ray,
+    shapes::{Placement, Shape},
+    tuple::{Point, Vector},
+    utils::EPSILON,
+};
+
+/// Flat triangle with precomputed edges and face normal
+#[derive(Debug, Clone, PartialEq)]
+pub struct Triangle {
+    placement: Placement,
+    pub material: Material,
+    p1: Point,
+    p2: Point,
+    p3: Point,
+    e1: Vector,
+    e2: Vector,
+    normal: Vector,
+}
+
+impl Triangle {
+    pub fn new(p1: Point, p2: Point, p3: Point) -> Self {
+        let e1 = p2 - p1;
+        let e2 = p3 - p1;
+        let normal = e2.cross(e1).normalize();
+        Triangle {
+            placement: Placement::default(),
+            material: Material::default(),
+            p1,
+            p2,
+            p3,
+            e1,
+            e2,
+            normal,
+        }
+    }
+
+    pub fn p1(&self) -> Point {
+        self.p1
+    }
+
+    pub fn p2(&self) -> Point {
+        self.p2
+    }
+
+    pub fn p3(&self) -> Point {
+        self.p3
+    }
+
+    pub fn e1(&self) -> Vector {
+        self.e1
+    }
+
+    pub fn e2(&self) -> Vector {
+        self.e2
+    }
+
+    pub fn normal(&self) -> Vector {
+        self.normal
+    }
+}
+
+/// Möller–Trumbore ray/triangle test.
+/// Returns the time of the hit along with its barycentric u and v.
+pub(crate) fn intersect_triangle(
+    r: Ray,
+    p1: Point,
+    e1: Vector,
+    e2: Vector,
+) -> Option<(f64, f64, f64)> {
+    let dir_cross_e2 = r.direction.cross(e2);
+    let det = e1.dot(dir_cross_e2);
+    // Ray is parallel to the triangle
+    if det.abs() < EPSILON {
+        return None;
+    }
+
+    let f = 1.0 / det;
+    let p1_to_origin = r.origin - p1;
+    let u = f * p1_to_origin.dot(dir_cross_e2);
+    if !(0.0..=1.0).contains(&u) {
+        return None;
+    }
+
+    let origin_cross_e1 = p1_to_origin.cross(e1);
+    let v = f * r.direction.dot(origin_cross_e1);
+    if v < 0.0 || (u + v) > 1.0 {
+        return None;
+    }
+
+    let t = f * e2.dot(origin_cross_e1);
+    Some((t, u, v))
+}
+
+impl Shape for Triangle {
+    fn placement(&self) -> &Placement {
+        &self.placement
+    }
+
+    fn placement_mut(&mut self) -> &mut Placement {
+        &mut self.placement
+    }
+
+    fn material(&self) -> &Material {
+        &self.material
+    }
+
+    fn material_mut(&mut self) -> &mut Material {
+        &mut self.material
+    }
+
+    fn local_intersect(&self, r: Ray) -> Vec<Intersection<'_, Triangle>> {
+        match intersect_triangle(r, self.p1, self.e1, self.e2) {
+            Some((t, u, v)) => vec![Intersection::with_uv(t, self, u, v)],
+            None => Vec::new(),
+        }
+    }
+
+    fn local_normal_at(&self, _p: Point) -> Vector {
+        self.normal
+    }
+
+    fn bounds(&self) -> BoundingBox {
+        BoundingBox::from_points(&[self.p1, self.p2, self.p3])
+    }
+}
+
+/// Triangle with a normal at each vertex. The normal at a hit is
+/// interpolated from them using the hit's barycentric u and v.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SmoothTriangle {
+    placement: Placement,
+    pub material: Material,
+    p1: Point,
+    p2: Point,
+    p3: Point,
+    n1: Vector,
+    n2: Vector,
+    n3: Vector,
+    e1: Vector,
+    e2: Vector,
+}
+
+impl SmoothTriangle {
+    pub fn new(p1: Point, p2: Point, p3: Point, n1: Vector, n2: Vector, n3: Vector) -> Self {
+        SmoothTriangle {
+            placement: Placement::default(),
+            material: Material::default(),
+            p1,
+            p2,
+            p3,
+            n1,
+            n2,
+            n3,
+            e1: p2 - p1,
+            e2: p3 - p1,
+        }
+    }
+
+    pub fn p1(&self) -> Point {
+        self.p1
+    }
+
+    pub fn p2(&self) -> Point {
+        self.p2
+    }
+
+    pub fn p3(&self) -> Point {
+        self.p3
+    }
+
+    pub fn n1(&self) -> Vector {
+        self.n1
+    }
+
+    pub fn n2(&self) -> Vector {
+        self.n2
+    }
+
+    pub fn n3(&self) -> Vector {
+        self.n3
+    }
+}
+
+impl Shape for SmoothTriangle {
+    fn placement(&self) -> &Placement {
+        &self.placement
+    }
+
+    fn placement_mut(&mut self) -> &mut Placement {
+        &mut self.placement
+    }
+
+    fn material(&self) -> &Material {
+        &self.material
+    }
+
+    fn material_mut(&mut self) -> &mut Material {
+        &mut self.material
+    }
+
+    fn local_intersect(&self, r: Ray) -> Vec<Intersection<'_, SmoothTriangle>> {
+        match intersect_triangle(r, self.p1, self.e1, self.e2) {
+            Some((t, u, v)) => vec![Intersection::with_uv(t, self, u, v)],
+            None => Vec::new(),
+        }
+    }
+
+    // Without a hit to interpolate from, fall back to the face normal
+    fn local_normal_at(&self, _p: Point) -> Vector {
+        self.e2.cross(self.e1).normalize()
+    }
+
+    fn local_normal_at_uv(&self, _p: Point, u: f64, v: f64) -> Vector {
+        self.n2 * u + self.n3 * v + self.n1 * (1.0 - u - v)
+    }
+
+    fn bounds(&self) -> BoundingBox {
+        BoundingBox::from_points(&[self.p1, self.p2, self.p3])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tuple::IsTuple;
+
+    use super::*;
+
+    fn triangle() -> Triangle {
+        Triangle::new(
+            Point::new(0.0, 1.0, 0.0),
+            Point::new(-1.0, 0.0, 0.0),
+            Point::new(1.0, 0.0, 0.0),
+        )
+    }
+
+    #[test]
+    fn test_create() {
+        let t = triangle();
+        assert_eq!(Point::new(0.0, 1.0, 0.0), t.p1());
+        assert_eq!(Point::new(-1.0, 0.0, 0.0), t.p2());
+        assert_eq!(Point::new(1.0, 0.0, 0.0), t.p3());
+        assert_eq!(Vector::new(-1.0, -1.0, 0.0), t.e1());
+        assert_eq!(Vector::new(1.0, -1.0, 0.0), t.e2());
+        assert_eq!(Vector::new(0.0, 0.0, -1.0), t.normal());
+    }
+
+    #[test]
+    fn test_normal() {
+        let t = triangle();
+        assert_eq!(t.normal(), t.local_normal_at(Point::new(0.0, 0.5, 0.0)));
+        assert_eq!(t.normal(), t.local_normal_at(Point::new(-0.5, 0.75, 0.0)));
+        assert_eq!(t.normal(), t.local_normal_at(Point::new(0.5, 0.25, 0.0)));
+    }
+
+    #[test]
+    fn test_intersect_parallel() {
+        let t = triangle();
+        let r = Ray::new(Point::new(0.0, -1.0, -2.0), Vector::new(0.0, 1.0, 0.0));
+        assert_eq!(0, t.local_intersect(r).len());
+    }
+
+    #[test]
+    fn test_intersect_misses_edges() {
+        let t = triangle();
+        // p1-p3 edge, p1-p2 edge and p2-p3 edge
+        for origin in [[1.0, 1.0, -2.0], [-1.0, 1.0, -2.0], [0.0, -1.0, -2.0]] {
+            let r = Ray::new(Point::from(origin), Vector::new(0.0, 0.0, 1.0));
+            assert_eq!(0, t.local_intersect(r).len());
+        }
+    }
+
+    #[test]
+    fn test_intersect_hit() {
+        let t = triangle();
+        let r = Ray::new(Point::new(0.0, 0.5, -2.0), Vector::new(0.0, 0.0, 1.0));
+        let xs = t.local_intersect(r);
+        assert_eq!(1, xs.len());
+        assert_eq!(2.0, xs[0].time);
+    }
+
+    fn smooth_triangle() -> SmoothTriangle {
+        SmoothTriangle::new(
+            Point::new(0.0, 1.0, 0.0),
+            Point::new(-1.0, 0.0, 0.0),
+            Point::new(1.0, 0.0, 0.0),
+            Vector::new(0.0, 1.0, 0.0),
+            Vector::new(-1.0, 0.0, 0.0),
+            Vector::new(1.0, 0.0, 0.0),
+        )
+    }
+
+    #[test]
+    fn test_smooth_create() {
+        let t = smooth_triangle();
+        assert_eq!(Point::new(0.0, 1.0, 0.0), t.p1());
+        assert_eq!(Point::new(-1.0, 0.0, 0.0), t.p2());
+        assert_eq!(Point::new(1.0, 0.0, 0.0), t.p3());
+        assert_eq!(Vector::new(0.0, 1.0, 0.0), t.n1());
+        assert_eq!(Vector::new(-1.0, 0.0, 0.0), t.n2());
+        assert_eq!(Vector::new(1.0, 0.0, 0.0), t.n3());
+    }
+
+    #[test]
+    fn test_smooth_intersect_stores_uv() {
+        let t = smooth_triangle();
+        let r = Ray::new(Point::new(-0.2, 0.3, -2.0), Vector::new(0.0, 0.0, 1.0));
+        let xs = t.local_intersect(r);
+        assert_eq!(1, xs.len());
+        assert!((0.45 - xs[0].u.unwrap()).abs() < EPSILON);
+        assert!((0.25 - xs[0].v.unwrap()).abs() < EPSILON);
+    }
+
+    #[test]
+    fn test_smooth_normal_interpolated() {
+        let t = smooth_triangle();
+        let n = t
+            .normal_at_uv(Point::new(0.0, 0.0, 0.0), 0.45, 0.25)
+            .unwrap();
+        assert_eq!(
+            Vector::new(-0.5547, 0.83205, 0.0).limit_precision(4),
+            n.limit_precision(4)
+        );
+    }
+
+    #[test]
+    fn test_bounds() {
+        let t = Triangle::new(
+            Point::new(-3.0, 7.0, 2.0),
+            Point::new(6.0, 2.0, -4.0),
+            Point::new(2.0, -1.0, -1.0),
+        );
+        let b = t.bounds();
+        assert_eq!(Point::new(-3.0, -1.0, -4.0), b.min);
+        assert_eq!(Point::new(6.0, 7.0, 2.0), b.max);
+        let b = smooth_triangle().bounds();
+        assert_eq!(Point::new(-1.0, 0.0, 0.0), b.min);
+        assert_eq!(Point::new(1.0, 1.0, 0.0), b.max);
+    }
+}
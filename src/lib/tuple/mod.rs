@@ -116,18 +116,12 @@ impl Neg for Tuple {
 const POINT_VALUE: f64 = 1.0;
 const VECTOR_VALUE: f64 = 0.0;
 
-trait IsPoint {}
-trait IsVector {}
-
 #[derive(Debug, Clone, Copy)]
 pub struct Point(pub Tuple);
 
 #[derive(Debug, Clone, Copy)]
 pub struct Vector(pub Tuple);
 
-impl IsPoint for Point {}
-impl IsVector for Vector {}
-
 impl IsTuple for Point {
     fn tuple(&self) -> Tuple {
         self.0
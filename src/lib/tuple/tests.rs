@@ -81,8 +81,8 @@ fn test_magnitude() {
 
 #[test]
 fn test_normalize() {
-    let test = vec![Vector::new(4.0, 0.0, 0.0), Vector::new(1.0, 2.0, 3.0)];
-    let expected = vec![
+    let test = [Vector::new(4.0, 0.0, 0.0), Vector::new(1.0, 2.0, 3.0)];
+    let expected = [
         Vector::new(1.0, 0.0, 0.0),
         Vector::new(
             1.0 / 14.0_f64.sqrt(),
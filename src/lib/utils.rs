@@ -1,11 +1,15 @@
 use num::Float;
 
+/// Offset used to nudge points off a surface so they don't self-intersect
+pub const EPSILON: f64 = 0.00001;
+
 /// Compare two floats for equality
 pub fn is_eq_float<T>(x: &T, y: &T) -> bool
 where
     T: Float,
 {
-    if (*x - *y).abs() <= Float::epsilon() {
+    // Exact match first, so that infinities are equal to themselves
+    if x == y || (*x - *y).abs() <= Float::epsilon() {
         return true;
     }
     false
@@ -1,53 +1,599 @@
-use crate::{lights::PointLight, objects::Object};
+use std::sync::OnceLock;
 
-#[derive(Debug, Clone, PartialEq)]
+use crate::{
+    bounds::BoundingBox,
+    bvh::Bvh,
+    color::{Color, CommonColor},
+    intersections::{Computations, Intersectable, Intersection},
+    lights::PointLight,
+    matrix::MatrixError,
+    objects::Object,
+    rays::Ray,
+    tuple::Point,
+};
+
+/// Bounces traced for reflections by default
+pub const DEFAULT_MAX_DEPTH: usize = 5;
+
+/// Objects are behind accessors so the bounds hierarchy can be rebuilt when
+/// they change.
+#[derive(Debug, Clone)]
 pub struct World {
-    pub objects: Vec<Object>,
+    objects: Vec<Object>,
     pub lights: Vec<PointLight>,
+    /// Bounces traced for reflections before giving up, so that rays
+    /// between two mirrors can't recurse forever
+    pub max_depth: usize,
+    bvh: OnceLock<Bvh>,
 }
 
 impl Default for World {
     fn default() -> Self {
-        World {
-            objects: Vec::new(),
-            lights: Vec::new(),
-        }
+        World::new(Vec::new(), Vec::new())
+    }
+}
+
+impl PartialEq for World {
+    fn eq(&self, other: &Self) -> bool {
+        self.objects == other.objects
+            && self.lights == other.lights
+            && self.max_depth == other.max_depth
     }
 }
 
 impl World {
     pub fn new(objects: Vec<Object>, lights: Vec<PointLight>) -> Self {
-        World { objects, lights }
+        World {
+            objects,
+            lights,
+            max_depth: DEFAULT_MAX_DEPTH,
+            bvh: OnceLock::new(),
+        }
+    }
+
+    pub fn objects(&self) -> &[Object] {
+        &self.objects
+    }
+
+    /// Mutable access to the objects, the bounds hierarchy is rebuilt on the
+    /// next intersection
+    pub fn objects_mut(&mut self) -> &mut Vec<Object> {
+        self.bvh = OnceLock::new();
+        &mut self.objects
+    }
+
+    pub fn add_object<T>(&mut self, object: T)
+    where
+        T: Into<Object>,
+    {
+        self.objects_mut().push(object.into());
+    }
+
+    fn bvh(&self) -> &Bvh {
+        self.bvh.get_or_init(|| {
+            let boxes: Vec<BoundingBox> = self
+                .objects
+                .iter()
+                .map(|o| o.parent_space_bounds())
+                .collect();
+            Bvh::build(&boxes)
+        })
+    }
+
+    /// Intersect the ray with every object whose bounds it passes through.
+    /// Returned intersections are sorted by time.
+    pub fn intersect(&self, r: Ray) -> Result<Vec<Intersection<'_, Object>>, MatrixError> {
+        let mut xs = Vec::new();
+        for i in self.bvh().candidates(r) {
+            xs.append(&mut self.objects[i].intersect(r)?);
+        }
+        xs.sort_by(|a, b| a.time.total_cmp(&b.time));
+        Ok(xs)
+    }
+
+    /// Color at the hit, summing the contribution of every light in the
+    /// world along with reflections and refractions. `remaining` is the
+    /// number of bounces left to trace.
+    pub fn shade_hit(&self, comps: &Computations, remaining: usize) -> Result<Color, MatrixError> {
+        let material = comps.object.material();
+        let mut color = CommonColor::Black.value();
+        for light in self.lights.iter() {
+            // Shade from just above the surface to avoid shadow acne
+            let in_shadow = self.is_shadowed(comps.over_point, light)?;
+            color = color
+                + material.lighting(
+                    comps.object,
+                    *light,
+                    comps.over_point,
+                    comps.eyev,
+                    comps.normalv,
+                    in_shadow,
+                )?;
+        }
+        let reflected = self.reflected_color(comps, remaining)?;
+        let refracted = self.refracted_color(comps, remaining)?;
+        // Surfaces both reflective and transparent split the light between
+        // the two depending on the viewing angle
+        if material.reflective > 0.0 && material.transparency > 0.0 {
+            let reflectance = comps.schlick();
+            return Ok(color + reflected * reflectance + refracted * (1.0 - reflectance));
+        }
+        Ok(color + reflected + refracted)
+    }
+
+    /// Color reflected off the hit, black for non-reflective surfaces or
+    /// once no bounces remain
+    pub fn reflected_color(
+        &self,
+        comps: &Computations,
+        remaining: usize,
+    ) -> Result<Color, MatrixError> {
+        let reflective = comps.object.material().reflective;
+        if reflective == 0.0 || remaining == 0 {
+            return Ok(CommonColor::Black.value());
+        }
+        let r = Ray::new(comps.over_point, comps.reflectv);
+        Ok(self.color_at_depth(r, remaining - 1)? * reflective)
+    }
+
+    /// Whether any object lies between the point and the light
+    pub fn is_shadowed(&self, point: Point, light: &PointLight) -> Result<bool, MatrixError> {
+        let v = light.position - point;
+        let distance = v.magnitude();
+        let r = Ray::new(point, v.normalize());
+        match Intersection::hit(self.intersect(r)?) {
+            Some(hit) => Ok(hit.time < distance),
+            None => Ok(false),
+        }
+    }
+
+    /// Color seen through the hit, black for opaque surfaces, under total
+    /// internal reflection or once no bounces remain
+    pub fn refracted_color(
+        &self,
+        comps: &Computations,
+        remaining: usize,
+    ) -> Result<Color, MatrixError> {
+        let transparency = comps.object.material().transparency;
+        if transparency == 0.0 || remaining == 0 {
+            return Ok(CommonColor::Black.value());
+        }
+
+        // Snell's law
+        let n_ratio = comps.n1 / comps.n2;
+        let cos_i = comps.eyev.dot(comps.normalv);
+        let sin2_t = n_ratio.powi(2) * (1.0 - cos_i.powi(2));
+        if sin2_t > 1.0 {
+            return Ok(CommonColor::Black.value());
+        }
+
+        let cos_t = (1.0 - sin2_t).sqrt();
+        let direction = comps.normalv * (n_ratio * cos_i - cos_t) - comps.eyev * n_ratio;
+        let r = Ray::new(comps.under_point, direction);
+        Ok(self.color_at_depth(r, remaining - 1)? * transparency)
+    }
+
+    /// Color seen along the ray. Black if the ray doesn't hit anything.
+    pub fn color_at(&self, r: Ray) -> Result<Color, MatrixError> {
+        self.color_at_depth(r, self.max_depth)
+    }
+
+    fn color_at_depth(&self, r: Ray, remaining: usize) -> Result<Color, MatrixError> {
+        let xs = self.intersect(r)?;
+        match Intersection::hit(xs.clone()) {
+            Some(hit) => self.shade_hit(&hit.prepare_computations(r, &xs)?, remaining),
+            None => Ok(CommonColor::Black.value()),
+        }
     }
 }
 
 #[cfg(test)]
 mod tests {
-    // use crate::{
-    //     color::Color,
-    //     spheres::Sphere,
-    //     tuple::{IsTuple, Point},
-    // };
+    use std::f64::consts::{FRAC_1_SQRT_2, SQRT_2};
+
+    use crate::{
+        matrix::Matrix,
+        planes::Plane,
+        shapes::Shape,
+        spheres::Sphere,
+        tuple::{IsTuple, Point, Vector},
+    };
 
     use super::*;
 
+    fn assert_send_sync<T: Send + Sync>() {}
+
+    fn default_world() -> World {
+        let light = PointLight::new(Point::new(-10.0, 10.0, -10.0), Color::new(1.0, 1.0, 1.0));
+        let mut s1 = Sphere::default();
+        s1.material.color = Color::new(0.8, 1.0, 0.6);
+        s1.material.diffuse = 0.7;
+        s1.material.specular = 0.2;
+        let mut s2 = Sphere::default();
+        s2.set_transform(Matrix::scaling(0.5, 0.5, 0.5));
+        World::new(vec![s1.into(), s2.into()], vec![light])
+    }
+
     #[test]
     fn test_default() {
         let w = World::default();
-        assert_eq!(0, w.objects.len());
+        assert_eq!(0, w.objects().len());
         assert_eq!(0, w.lights.len());
+        assert_eq!(DEFAULT_MAX_DEPTH, w.max_depth);
+    }
+
+    #[test]
+    fn test_create() {
+        let w = default_world();
+        assert_eq!(2, w.objects().len());
+        assert_eq!(1, w.lights.len());
+    }
+
+    #[test]
+    fn test_intersect() {
+        let w = default_world();
+        let r = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        let xs = w.intersect(r).unwrap();
+        assert_eq!(vec![4.0, 4.5, 5.5, 6.0], Intersection::intersections(xs));
+    }
+
+    #[test]
+    fn test_intersect_many_objects() {
+        // Grid of spheres with a floor, enough to need several levels
+        let mut w = World::default();
+        w.add_object(Plane::new());
+        for x in 0..10 {
+            for z in 0..10 {
+                let mut s = Sphere::default();
+                s.set_transform(Matrix::translation(x as f64 * 3.0, 1.0, z as f64 * 3.0));
+                w.add_object(s);
+            }
+        }
+        let r = Ray::new(Point::new(9.0, 1.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        let xs = w.intersect(r).unwrap();
+        // Every sphere in the x = 9 column, plus the floor the ray runs along
+        assert_eq!(20, xs.len());
+        assert_eq!(4.0, xs[0].time);
+
+        let r = Ray::new(Point::new(9.0, 5.0, 0.0), Vector::new(0.0, -1.0, 0.0));
+        let xs = w.intersect(r).unwrap();
+        assert_eq!(vec![3.0, 5.0, 5.0], Intersection::intersections(xs));
+    }
+
+    #[test]
+    fn test_add_object_after_intersect() {
+        let mut w = default_world();
+        let r = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        assert_eq!(4, w.intersect(r).unwrap().len());
+
+        let mut s = Sphere::default();
+        s.set_transform(Matrix::translation(0.0, 0.0, 10.0));
+        w.add_object(s);
+        assert_eq!(6, w.intersect(r).unwrap().len());
+
+        w.objects_mut()[2].set_transform(Matrix::translation(10.0, 0.0, 10.0));
+        assert_eq!(4, w.intersect(r).unwrap().len());
+    }
+
+    #[test]
+    fn test_shade_hit() {
+        let w = default_world();
+        let r = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        let i = Intersection::new(4.0, &w.objects()[0]);
+        let comps = i.prepare_computations(r, &[i]).unwrap();
+        assert_eq!(
+            Color::new(0.38066, 0.47583, 0.2855),
+            w.shade_hit(&comps, w.max_depth).unwrap().limit_precision(5)
+        );
     }
 
-    // #[test]
-    // fn test_create() {
-    //     let light = PointLight::new(Point::new(-10.0, 10.0, -10.0), Color::new(1.0, 1.0, 1.0));
-    //     let s1 = Sphere::default();
-    //     let s2 = Sphere::default();
-    //     let mut v_s = Vec::new();
-    //     v_s.push(s1);
-    //     v_s.push(s2);
-    //     let mut v_l = Vec::new();
-    //     v_l.push(light);
-    //     let w = World::new(v_s, v_l);
-    // }
+    #[test]
+    fn test_shade_hit_inside() {
+        let mut w = default_world();
+        w.lights = vec![PointLight::new(
+            Point::new(0.0, 0.25, 0.0),
+            Color::new(1.0, 1.0, 1.0),
+        )];
+        let r = Ray::new(Point::new(0.0, 0.0, 0.0), Vector::new(0.0, 0.0, 1.0));
+        let i = Intersection::new(0.5, &w.objects()[1]);
+        let comps = i.prepare_computations(r, &[i]).unwrap();
+        assert_eq!(
+            Color::new(0.90498, 0.90498, 0.90498),
+            w.shade_hit(&comps, w.max_depth).unwrap().limit_precision(5)
+        );
+    }
+
+    #[test]
+    fn test_shade_hit_multiple_lights() {
+        let mut w = default_world();
+        let r = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        let i = Intersection::new(4.0, &w.objects()[0]);
+        let single = w.shade_hit(&i.prepare_computations(r, &[i]).unwrap(), w.max_depth);
+        w.lights.push(w.lights[0]);
+        let i = Intersection::new(4.0, &w.objects()[0]);
+        let comps = i.prepare_computations(r, &[i]).unwrap();
+        assert_eq!(
+            single.unwrap() * 2.0,
+            w.shade_hit(&comps, w.max_depth).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_color_at() {
+        let w = default_world();
+
+        // Ray misses
+        let r1 = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 1.0, 0.0));
+        assert_eq!(Color::new(0.0, 0.0, 0.0), w.color_at(r1).unwrap());
+
+        // Ray hits
+        let r2 = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        assert_eq!(
+            Color::new(0.38066, 0.47583, 0.2855),
+            w.color_at(r2).unwrap().limit_precision(5)
+        );
+    }
+
+    #[test]
+    fn test_color_at_intersection_behind_ray() {
+        let mut w = default_world();
+        for object in w.objects_mut().iter_mut() {
+            object.material_mut().ambient = 1.0;
+        }
+        let r = Ray::new(Point::new(0.0, 0.0, 0.75), Vector::new(0.0, 0.0, -1.0));
+        assert_eq!(w.objects()[1].material().color, w.color_at(r).unwrap());
+    }
+
+    #[test]
+    fn test_is_shadowed() {
+        let w = default_world();
+        let light = &w.lights[0];
+
+        // Nothing is collinear with point and light
+        assert!(!w.is_shadowed(Point::new(0.0, 10.0, 0.0), light).unwrap());
+
+        // Object between the point and the light
+        assert!(w.is_shadowed(Point::new(10.0, -10.0, 10.0), light).unwrap());
+
+        // Object behind the light
+        assert!(!w
+            .is_shadowed(Point::new(-20.0, 20.0, -20.0), light)
+            .unwrap());
+
+        // Object behind the point
+        assert!(!w.is_shadowed(Point::new(-2.0, 2.0, -2.0), light).unwrap());
+    }
+
+    #[test]
+    fn test_shade_hit_in_shadow() {
+        let light = PointLight::new(Point::new(0.0, 0.0, -10.0), Color::new(1.0, 1.0, 1.0));
+        let s1 = Sphere::default();
+        let mut s2 = Sphere::default();
+        s2.set_transform(Matrix::translation(0.0, 0.0, 10.0));
+        let w = World::new(vec![s1.into(), s2.into()], vec![light]);
+        let r = Ray::new(Point::new(0.0, 0.0, 5.0), Vector::new(0.0, 0.0, 1.0));
+        let i = Intersection::new(4.0, &w.objects()[1]);
+        let comps = i.prepare_computations(r, &[i]).unwrap();
+        assert_eq!(
+            Color::new(0.1, 0.1, 0.1),
+            w.shade_hit(&comps, w.max_depth).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_shade_hit_offsets_point() {
+        // Without the offset the hit point would shadow itself
+        let light = PointLight::new(Point::new(0.0, 0.0, -10.0), Color::new(1.0, 1.0, 1.0));
+        let mut s = Sphere::default();
+        s.set_transform(Matrix::translation(0.0, 0.0, 1.0));
+        let w = World::new(vec![s.into()], vec![light]);
+        let r = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        let i = Intersection::new(5.0, &w.objects()[0]);
+        let comps = i.prepare_computations(r, &[i]).unwrap();
+        assert_eq!(
+            Color::new(1.9, 1.9, 1.9),
+            w.shade_hit(&comps, w.max_depth).unwrap()
+        );
+    }
+
+    // Default world with a half reflective plane below the spheres
+    fn reflective_world() -> World {
+        let mut w = default_world();
+        let mut p = Plane::new();
+        p.material.reflective = 0.5;
+        p.set_transform(Matrix::translation(0.0, -1.0, 0.0));
+        w.add_object(p);
+        w
+    }
+
+    #[test]
+    fn test_reflected_color_non_reflective() {
+        let mut w = default_world();
+        w.objects_mut()[1].material_mut().ambient = 1.0;
+        let r = Ray::new(Point::new(0.0, 0.0, 0.0), Vector::new(0.0, 0.0, 1.0));
+        let i = Intersection::new(1.0, &w.objects()[1]);
+        let comps = i.prepare_computations(r, &[i]).unwrap();
+        assert_eq!(
+            Color::new(0.0, 0.0, 0.0),
+            w.reflected_color(&comps, w.max_depth).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_reflected_color() {
+        let w = reflective_world();
+        let r = Ray::new(
+            Point::new(0.0, 0.0, -3.0),
+            Vector::new(0.0, -FRAC_1_SQRT_2, FRAC_1_SQRT_2),
+        );
+        let i = Intersection::new(SQRT_2, &w.objects()[2]);
+        let comps = i.prepare_computations(r, &[i]).unwrap();
+        assert_eq!(
+            Color::new(0.1903, 0.2379, 0.1427),
+            w.reflected_color(&comps, w.max_depth)
+                .unwrap()
+                .limit_precision(4)
+        );
+        assert_eq!(
+            Color::new(0.87676, 0.92434, 0.82917),
+            w.shade_hit(&comps, w.max_depth).unwrap().limit_precision(5)
+        );
+    }
+
+    #[test]
+    fn test_reflected_color_no_bounces_left() {
+        let w = reflective_world();
+        let r = Ray::new(
+            Point::new(0.0, 0.0, -3.0),
+            Vector::new(0.0, -FRAC_1_SQRT_2, FRAC_1_SQRT_2),
+        );
+        let i = Intersection::new(SQRT_2, &w.objects()[2]);
+        let comps = i.prepare_computations(r, &[i]).unwrap();
+        assert_eq!(
+            Color::new(0.0, 0.0, 0.0),
+            w.reflected_color(&comps, 0).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_color_at_mutually_reflective() {
+        // Ray bounces between two parallel mirrors until it runs out of depth
+        let light = PointLight::new(Point::new(0.0, 0.0, 0.0), Color::new(1.0, 1.0, 1.0));
+        let mut lower = Plane::new();
+        lower.material.reflective = 1.0;
+        lower.set_transform(Matrix::translation(0.0, -1.0, 0.0));
+        let mut upper = Plane::new();
+        upper.material.reflective = 1.0;
+        upper.set_transform(Matrix::translation(0.0, 1.0, 0.0));
+        let w = World::new(vec![lower.into(), upper.into()], vec![light]);
+        let r = Ray::new(Point::new(0.0, 0.0, 0.0), Vector::new(0.0, 1.0, 0.0));
+        let deep = w.color_at(r).unwrap();
+
+        // Every extra bounce picks up more light
+        let mut shallow = w.clone();
+        shallow.max_depth = 1;
+        assert!(deep.r > shallow.color_at(r).unwrap().r);
+    }
+
+    // Default world with its outer sphere made of glass
+    fn glass_world() -> World {
+        let mut w = default_world();
+        let m = w.objects_mut()[0].material_mut();
+        m.transparency = 1.0;
+        m.refractive_index = 1.5;
+        w
+    }
+
+    #[test]
+    fn test_refracted_color_opaque() {
+        let w = default_world();
+        let r = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        let xs = vec![
+            Intersection::new(4.0, &w.objects()[0]),
+            Intersection::new(6.0, &w.objects()[0]),
+        ];
+        let comps = xs[0].prepare_computations(r, &xs).unwrap();
+        assert_eq!(
+            Color::new(0.0, 0.0, 0.0),
+            w.refracted_color(&comps, 5).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_refracted_color_no_bounces_left() {
+        let w = glass_world();
+        let r = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        let xs = vec![
+            Intersection::new(4.0, &w.objects()[0]),
+            Intersection::new(6.0, &w.objects()[0]),
+        ];
+        let comps = xs[0].prepare_computations(r, &xs).unwrap();
+        assert_eq!(
+            Color::new(0.0, 0.0, 0.0),
+            w.refracted_color(&comps, 0).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_refracted_color_total_internal_reflection() {
+        let w = glass_world();
+        let r = Ray::new(
+            Point::new(0.0, 0.0, FRAC_1_SQRT_2),
+            Vector::new(0.0, 1.0, 0.0),
+        );
+        let xs = vec![
+            Intersection::new(-FRAC_1_SQRT_2, &w.objects()[0]),
+            Intersection::new(FRAC_1_SQRT_2, &w.objects()[0]),
+        ];
+        // Inside the sphere, so the second intersection is the one to look at
+        let comps = xs[1].prepare_computations(r, &xs).unwrap();
+        assert_eq!(
+            Color::new(0.0, 0.0, 0.0),
+            w.refracted_color(&comps, 5).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_refracted_color() {
+        // Glass inner sphere, the refracted ray ends up inside the outer
+        // sphere which is fully ambient and shadowed by itself
+        let mut w = default_world();
+        w.objects_mut()[0].material_mut().ambient = 1.0;
+        let m = w.objects_mut()[1].material_mut();
+        m.transparency = 1.0;
+        m.refractive_index = 1.5;
+        let r = Ray::new(Point::new(0.0, 0.0, 0.1), Vector::new(0.0, 1.0, 0.0));
+        let xs = vec![
+            Intersection::new(-0.9899, &w.objects()[0]),
+            Intersection::new(-0.4899, &w.objects()[1]),
+            Intersection::new(0.4899, &w.objects()[1]),
+            Intersection::new(0.9899, &w.objects()[0]),
+        ];
+        let comps = xs[2].prepare_computations(r, &xs).unwrap();
+        assert_eq!(
+            w.objects()[0].material().color,
+            w.refracted_color(&comps, 5).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_shade_hit_transparent() {
+        let mut w = default_world();
+        let mut floor = Plane::new();
+        floor.set_transform(Matrix::translation(0.0, -1.0, 0.0));
+        floor.material.transparency = 0.5;
+        floor.material.refractive_index = 1.5;
+        w.add_object(floor);
+        let mut ball = Sphere::default();
+        ball.material.color = Color::new(1.0, 0.0, 0.0);
+        ball.material.ambient = 0.5;
+        ball.set_transform(Matrix::translation(0.0, -3.5, -0.5));
+        w.add_object(ball);
+
+        let r = Ray::new(
+            Point::new(0.0, 0.0, -3.0),
+            Vector::new(0.0, -FRAC_1_SQRT_2, FRAC_1_SQRT_2),
+        );
+        let xs = vec![Intersection::new(SQRT_2, &w.objects()[2])];
+        let comps = xs[0].prepare_computations(r, &xs).unwrap();
+        assert_eq!(
+            Color::new(0.9364, 0.6864, 0.6864),
+            w.shade_hit(&comps, 5).unwrap().limit_precision(4)
+        );
+
+        // Reflective as well, blended using Schlick's approximation
+        w.objects_mut()[2].material_mut().reflective = 0.5;
+        let xs = vec![Intersection::new(SQRT_2, &w.objects()[2])];
+        let comps = xs[0].prepare_computations(r, &xs).unwrap();
+        assert_eq!(
+            Color::new(0.9339, 0.6964, 0.6924),
+            w.shade_hit(&comps, 5).unwrap().limit_precision(4)
+        );
+    }
+
+    #[test]
+    fn test_send_sync() {
+        // Worlds can be shared between rendering threads
+        assert_send_sync::<World>();
+    }
 }
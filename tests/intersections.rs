@@ -1,14 +1,27 @@
 #[cfg(test)]
-
 mod tests {
+    use std::f64::consts::FRAC_1_SQRT_2;
+
     use raytracer::{
         intersections::{Intersectable, Intersection},
         matrix::Matrix,
+        objects::Object,
         rays::Ray,
+        shapes::Shape,
         spheres::Sphere,
+        triangles::SmoothTriangle,
         tuple::{IsTuple, Point, Vector},
+        utils::EPSILON,
     };
 
+    fn glass_sphere(transform: Matrix<f64>, refractive_index: f64) -> Object {
+        let mut s = Sphere::default();
+        s.set_transform(transform);
+        s.material.transparency = 1.0;
+        s.material.refractive_index = refractive_index;
+        s.into()
+    }
+
     #[test]
     fn test_sphere_ray_intersection() {
         let r1 = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
@@ -52,7 +65,7 @@ mod tests {
         let r = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
         let mut s = Sphere::new(Point::new(0.0, 0.0, 0.0), 1.0);
         s.set_transform(Matrix::scaling(2.0, 2.0, 2.0));
-        let xs = s.intersect(r.clone()).unwrap();
+        let xs = s.intersect(r).unwrap();
         assert_eq!(2, xs.len());
         assert_eq!(3.0, xs[0].time);
         assert_eq!(7.0, xs[1].time);
@@ -70,7 +83,7 @@ mod tests {
         let mut is1: Vec<Intersection<Sphere>> = Vec::new();
         let i11 = Intersection::new(1.0, &s);
         let i12 = Intersection::new(2.0, &s);
-        is1.push(i11.clone());
+        is1.push(i11);
         is1.push(i12);
         assert_eq!(i11, Intersection::hit(is1).unwrap());
 
@@ -79,7 +92,7 @@ mod tests {
         let i21 = Intersection::new(-1.0, &s);
         let i22 = Intersection::new(2.0, &s);
         is2.push(i21);
-        is2.push(i22.clone());
+        is2.push(i22);
         assert_eq!(i22, Intersection::hit(is2).unwrap());
 
         // All intersection with negative t
@@ -99,7 +112,155 @@ mod tests {
         is4.push(i41);
         is4.push(i42);
         is4.push(i43);
-        is4.push(i44.clone());
+        is4.push(i44);
         assert_eq!(i44, Intersection::hit(is4).unwrap());
     }
+
+    #[test]
+    fn test_prepare_computations() {
+        let r = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        let shape = Object::from(Sphere::default());
+        let i = Intersection::new(4.0, &shape);
+        let comps = i.prepare_computations(r, &[i]).unwrap();
+        assert_eq!(i.time, comps.time);
+        assert_eq!(&shape, comps.object);
+        assert_eq!(Point::new(0.0, 0.0, -1.0), comps.point);
+        assert_eq!(Vector::new(0.0, 0.0, -1.0), comps.eyev);
+        assert_eq!(Vector::new(0.0, 0.0, -1.0), comps.normalv);
+        assert_eq!(Vector::new(0.0, 0.0, -1.0), comps.reflectv);
+        assert!(!comps.inside);
+    }
+
+    #[test]
+    fn test_prepare_computations_inside() {
+        let r = Ray::new(Point::new(0.0, 0.0, 0.0), Vector::new(0.0, 0.0, 1.0));
+        let shape = Object::from(Sphere::default());
+        let i = Intersection::new(1.0, &shape);
+        let comps = i.prepare_computations(r, &[i]).unwrap();
+        assert_eq!(Point::new(0.0, 0.0, 1.0), comps.point);
+        assert_eq!(Vector::new(0.0, 0.0, -1.0), comps.eyev);
+        assert!(comps.inside);
+        // Normal is inverted to face the eye
+        assert_eq!(Vector::new(0.0, 0.0, -1.0), comps.normalv);
+    }
+
+    #[test]
+    fn test_prepare_computations_over_and_under_point() {
+        let r = Ray::new(Point::new(0.0, 0.0, -5.0), Vector::new(0.0, 0.0, 1.0));
+        let shape = glass_sphere(Matrix::translation(0.0, 0.0, 1.0), 1.5);
+        let i = Intersection::new(5.0, &shape);
+        let comps = i.prepare_computations(r, &[i]).unwrap();
+        assert!(comps.over_point.0.z < -EPSILON / 2.0);
+        assert!(comps.point.0.z > comps.over_point.0.z);
+        assert!(comps.under_point.0.z > EPSILON / 2.0);
+        assert!(comps.point.0.z < comps.under_point.0.z);
+    }
+
+    #[test]
+    fn test_prepare_computations_refractive_indices() {
+        let a = glass_sphere(Matrix::scaling(2.0, 2.0, 2.0), 1.5);
+        let b = glass_sphere(Matrix::translation(0.0, 0.0, -0.25), 2.0);
+        let c = glass_sphere(Matrix::translation(0.0, 0.0, 0.25), 2.5);
+        let r = Ray::new(Point::new(0.0, 0.0, -4.0), Vector::new(0.0, 0.0, 1.0));
+        let xs = vec![
+            Intersection::new(2.0, &a),
+            Intersection::new(2.75, &b),
+            Intersection::new(3.25, &c),
+            Intersection::new(4.75, &b),
+            Intersection::new(5.25, &c),
+            Intersection::new(6.0, &a),
+        ];
+        let expected = [
+            (1.0, 1.5),
+            (1.5, 2.0),
+            (2.0, 2.5),
+            (2.5, 2.5),
+            (2.5, 1.5),
+            (1.5, 1.0),
+        ];
+        for (i, (n1, n2)) in xs.iter().zip(expected) {
+            let comps = i.prepare_computations(r, &xs).unwrap();
+            assert_eq!(n1, comps.n1);
+            assert_eq!(n2, comps.n2);
+        }
+    }
+
+    fn smooth_triangle() -> Object {
+        SmoothTriangle::new(
+            Point::new(0.0, 1.0, 0.0),
+            Point::new(-1.0, 0.0, 0.0),
+            Point::new(1.0, 0.0, 0.0),
+            Vector::new(0.0, 1.0, 0.0),
+            Vector::new(-1.0, 0.0, 0.0),
+            Vector::new(1.0, 0.0, 0.0),
+        )
+        .into()
+    }
+
+    #[test]
+    fn test_intersection_with_uv() {
+        let s = Sphere::default();
+        let i = Intersection::with_uv(3.5, &s, 0.2, 0.4);
+        assert_eq!(Some(0.2), i.u);
+        assert_eq!(Some(0.4), i.v);
+        assert_eq!(None, Intersection::new(3.5, &s).u);
+    }
+
+    #[test]
+    fn test_object_intersect_keeps_uv() {
+        let t = smooth_triangle();
+        let r = Ray::new(Point::new(-0.2, 0.3, -2.0), Vector::new(0.0, 0.0, 1.0));
+        let xs = t.intersect(r).unwrap();
+        assert_eq!(1, xs.len());
+        assert!((0.45 - xs[0].u.unwrap()).abs() < EPSILON);
+        assert!((0.25 - xs[0].v.unwrap()).abs() < EPSILON);
+    }
+
+    #[test]
+    fn test_prepare_computations_smooth_triangle() {
+        let t = smooth_triangle();
+        let i = Intersection::with_uv(1.0, &t, 0.45, 0.25);
+        let r = Ray::new(Point::new(-0.2, 0.3, -2.0), Vector::new(0.0, 0.0, 1.0));
+        let comps = i.prepare_computations(r, &[i]).unwrap();
+        assert_eq!(
+            Vector::new(-0.5547, 0.83205, 0.0).limit_precision(4),
+            comps.normalv.limit_precision(4)
+        );
+    }
+
+    #[test]
+    fn test_schlick_total_internal_reflection() {
+        let shape = glass_sphere(Matrix::identity(4, 1.0), 1.5);
+        let r = Ray::new(
+            Point::new(0.0, 0.0, FRAC_1_SQRT_2),
+            Vector::new(0.0, 1.0, 0.0),
+        );
+        let xs = vec![
+            Intersection::new(-FRAC_1_SQRT_2, &shape),
+            Intersection::new(FRAC_1_SQRT_2, &shape),
+        ];
+        let comps = xs[1].prepare_computations(r, &xs).unwrap();
+        assert_eq!(1.0, comps.schlick());
+    }
+
+    #[test]
+    fn test_schlick_perpendicular() {
+        let shape = glass_sphere(Matrix::identity(4, 1.0), 1.5);
+        let r = Ray::new(Point::new(0.0, 0.0, 0.0), Vector::new(0.0, 1.0, 0.0));
+        let xs = vec![
+            Intersection::new(-1.0, &shape),
+            Intersection::new(1.0, &shape),
+        ];
+        let comps = xs[1].prepare_computations(r, &xs).unwrap();
+        assert!((0.04 - comps.schlick()).abs() < EPSILON);
+    }
+
+    #[test]
+    fn test_schlick_small_angle() {
+        let shape = glass_sphere(Matrix::identity(4, 1.0), 1.5);
+        let r = Ray::new(Point::new(0.0, 0.99, -2.0), Vector::new(0.0, 0.0, 1.0));
+        let xs = vec![Intersection::new(1.8589, &shape)];
+        let comps = xs[0].prepare_computations(r, &xs).unwrap();
+        assert!((0.48873 - comps.schlick()).abs() < 0.0001);
+    }
 }